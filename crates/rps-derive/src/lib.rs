@@ -0,0 +1,85 @@
+//! Derive macros for the RPS API wrappers.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, GenericParam, Lifetime, Type};
+
+/// Derives `rps::cmd::NodeArgs` to decode node arguments from a command callback context.
+///
+/// Fields are decoded in declaration order, one field per node parameter. Slice reference fields (`&'a [T]`) decode
+/// array parameters, all other fields are copied out of the context.
+#[proc_macro_derive(NodeArgs)]
+pub fn derive_node_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "NodeArgs can only be derived for structs",
+            ))
+        }
+    };
+
+    let decode = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ty {
+            Type::Reference(reference) if matches!(*reference.elem, Type::Slice(_)) => {
+                quote! { context.arg_array(#index)? }
+            }
+            _ => quote! { *context.arg(#index)? },
+        });
+
+    let body = match fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote! { Self { #(#names: #decode),* } }
+        }
+        Fields::Unnamed(_) => quote! { Self(#(#decode),*) },
+        Fields::Unit => quote! { Self },
+    };
+
+    let num_args = fields.len();
+    let name = &input.ident;
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The context lifetime must outlive every lifetime of the struct, so slices can be borrowed from it.
+    let context = Lifetime::new("'__context", Span::call_site());
+    let mut generics = input.generics.clone();
+    let bounds = generics
+        .lifetimes()
+        .map(|param| param.lifetime.clone())
+        .collect::<Vec<_>>();
+    generics.params.insert(
+        0,
+        GenericParam::Lifetime(syn::LifetimeParam::new(context.clone())),
+    );
+    if let Some(GenericParam::Lifetime(param)) = generics.params.first_mut() {
+        param.bounds.extend(bounds);
+    }
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rps::cmd::NodeArgs<#context> for #name #ty_generics #where_clause {
+            fn from_context(
+                context: &::rps::cmd::CmdContext<#context>,
+            ) -> ::std::result::Result<Self, ::rps::Error> {
+                if context.num_args() != #num_args {
+                    return ::std::result::Result::Err(::rps::Error::TypeMismatch);
+                }
+
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
@@ -0,0 +1,253 @@
+//! Custom memory allocators for RPS devices.
+
+use std::{
+    alloc::{self, Layout},
+    mem,
+    os::raw::c_void,
+    ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::guard;
+use rps_sys as ffi;
+
+/// Allocator of the CPU memory used by an RPS device.
+///
+/// # Safety
+///
+/// Implementations must return blocks of at least the requested size and alignment (or null on failure), which stay
+/// valid until freed.
+pub unsafe trait Allocator: Send + Sync {
+    /// Allocates a block of memory, returns null on failure.
+    fn alloc(&self, size: usize, alignment: usize) -> *mut u8;
+
+    /// Frees a block of memory.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null block returned by this allocator which is not freed yet.
+    unsafe fn free(&self, ptr: *mut u8);
+
+    /// Resizes a block of memory, returns null on failure leaving the old block untouched.
+    ///
+    /// The default implementation allocates a new block and copies the contents over.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null block of `old_size` bytes returned by this allocator which is not freed yet.
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        old_size: usize,
+        new_size: usize,
+        alignment: usize,
+    ) -> *mut u8 {
+        let new_ptr = self.alloc(new_size, alignment);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, old_size.min(new_size));
+            self.free(ptr);
+        }
+
+        new_ptr
+    }
+}
+
+/// Size of the header storing the size and alignment of a block.
+const HEADER_SIZE: usize = 2 * mem::size_of::<usize>();
+
+/// Allocator using the Rust global allocator.
+///
+/// Every block is prefixed with a header storing its size and alignment, as RPS doesn't pass them when freeing.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemAllocator;
+
+impl SystemAllocator {
+    /// Returns the size requested for a block.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null block returned by this allocator which is not freed yet.
+    unsafe fn size(ptr: *mut u8) -> usize {
+        *(ptr.sub(HEADER_SIZE) as *const usize)
+    }
+
+    fn layout(size: usize, alignment: usize) -> Option<Layout> {
+        // The header is placed right before the block, the block offset is the alignment to keep it aligned.
+        let alignment = alignment.max(HEADER_SIZE).checked_next_power_of_two()?;
+        Layout::from_size_align(size.checked_add(alignment)?, alignment).ok()
+    }
+}
+
+unsafe impl Allocator for SystemAllocator {
+    fn alloc(&self, size: usize, alignment: usize) -> *mut u8 {
+        let layout = match Self::layout(size, alignment) {
+            Some(layout) => layout,
+            None => return ptr::null_mut(),
+        };
+
+        unsafe {
+            let base = alloc::alloc(layout);
+            if base.is_null() {
+                return base;
+            }
+
+            let ptr = base.add(layout.align());
+            let header = ptr.sub(HEADER_SIZE) as *mut usize;
+            header.write(size);
+            header.add(1).write(layout.align());
+
+            ptr
+        }
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        let header = ptr.sub(HEADER_SIZE) as *const usize;
+        let size = header.read();
+        let alignment = header.add(1).read();
+
+        let layout = Layout::from_size_align_unchecked(size + alignment, alignment);
+        alloc::dealloc(ptr.sub(alignment), layout);
+    }
+}
+
+/// Allocator tracking the number of bytes allocated by RPS.
+///
+/// Share it between the device and the code reporting the statistics with an [Arc].
+#[derive(Debug, Default)]
+pub struct CountingAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl CountingAllocator {
+    /// Creates a new counting allocator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of bytes currently allocated.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Returns the highest number of bytes allocated at once.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl Allocator for CountingAllocator {
+    fn alloc(&self, size: usize, alignment: usize) -> *mut u8 {
+        let ptr = SystemAllocator.alloc(size, alignment);
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(size, Ordering::Relaxed) + size;
+            self.peak.fetch_max(current, Ordering::Relaxed);
+        }
+
+        ptr
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        self.current
+            .fetch_sub(SystemAllocator::size(ptr), Ordering::Relaxed);
+        SystemAllocator.free(ptr);
+    }
+}
+
+/// Allocator state passed to RPS as the allocator context.
+pub(crate) type AllocatorContext = Box<Arc<dyn Allocator>>;
+
+/// Returns the raw allocator forwarding to `allocator`.
+///
+/// The allocator context must outlive the device the raw allocator is passed to.
+pub(crate) fn raw(allocator: &AllocatorContext) -> ffi::RpsAllocator {
+    ffi::RpsAllocator {
+        pfnAlloc: Some(alloc_callback),
+        pfnFree: Some(free_callback),
+        pfnRealloc: Some(realloc_callback),
+        pContext: allocator.as_ref() as *const Arc<dyn Allocator> as *mut c_void,
+    }
+}
+
+unsafe fn get_allocator<'a>(context: *mut c_void) -> &'a dyn Allocator {
+    (*(context as *const Arc<dyn Allocator>)).as_ref()
+}
+
+unsafe extern "C" fn alloc_callback(
+    context: *mut c_void,
+    size: usize,
+    alignment: usize,
+) -> *mut c_void {
+    guard::catch(ptr::null_mut(), || {
+        get_allocator(context).alloc(size, alignment) as *mut c_void
+    })
+}
+
+unsafe extern "C" fn free_callback(context: *mut c_void, buffer: *mut c_void) {
+    if !buffer.is_null() {
+        guard::catch((), || get_allocator(context).free(buffer as *mut u8));
+    }
+}
+
+unsafe extern "C" fn realloc_callback(
+    context: *mut c_void,
+    old_buffer: *mut c_void,
+    old_size: usize,
+    new_size: usize,
+    alignment: usize,
+) -> *mut c_void {
+    let allocator = get_allocator(context);
+
+    guard::catch(ptr::null_mut(), || {
+        if old_buffer.is_null() {
+            allocator.alloc(new_size, alignment) as *mut c_void
+        } else {
+            allocator.realloc(old_buffer as *mut u8, old_size, new_size, alignment) as *mut c_void
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{runtime, DeviceBuilder};
+
+    struct Dummy;
+    impl runtime::Callbacks for Dummy {}
+
+    #[test]
+    fn system_allocator() {
+        for alignment in [1, 8, 64, 256] {
+            let ptr = SystemAllocator.alloc(100, alignment);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % alignment, 0);
+
+            unsafe {
+                ptr.write_bytes(0xCD, 100);
+                let ptr = SystemAllocator.realloc(ptr, 100, 200, alignment);
+                assert_eq!(*ptr.add(99), 0xCD);
+                assert_eq!(SystemAllocator::size(ptr), 200);
+                SystemAllocator.free(ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn counting_allocator() {
+        let allocator = Arc::new(CountingAllocator::new());
+
+        let device = DeviceBuilder::new()
+            .allocator(allocator.clone())
+            .build_null(Arc::new(Dummy))
+            .unwrap();
+
+        assert!(allocator.current() > 0);
+        assert!(allocator.peak() >= allocator.current());
+
+        drop(device);
+        assert_eq!(allocator.current(), 0);
+    }
+}
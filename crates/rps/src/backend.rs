@@ -0,0 +1,357 @@
+//! Custom runtime backends implemented in Rust.
+
+use std::{ffi::CStr, ops::Range};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::{self, CmdKind},
+    graph::{CommandBatch, RenderGraph},
+    runtime::AccessFlags,
+    signature::NodeFlags,
+    Error,
+};
+use rps_sys as ffi;
+
+/// Subresource range of an image.
+pub type SubresourceRange = ffi::RpsSubresourceRange;
+
+/// Serde definition of [SubresourceRange], for use with `#[serde(with = "rps::backend::SubresourceRangeDef")]`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "SubresourceRange")]
+#[allow(non_snake_case)]
+pub struct SubresourceRangeDef {
+    /// First mipmap level.
+    pub baseMipLevel: u16,
+    /// Number of mipmap levels.
+    pub mipLevels: u16,
+    /// First array layer.
+    pub baseArrayLayer: u32,
+    /// Number of array layers.
+    pub arrayLayers: u32,
+}
+
+/// Transition of a resource between two accesses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transition {
+    /// Index of the resource in the render graph.
+    pub resource_index: u32,
+    /// Access of the resource before the transition.
+    pub prev_access: AccessFlags,
+    /// Access of the resource after the transition.
+    pub next_access: AccessFlags,
+    /// Subresources affected by the transition.
+    #[cfg_attr(feature = "serde", serde(with = "SubresourceRangeDef"))]
+    pub range: SubresourceRange,
+}
+
+impl Transition {
+    /// Returns the transition of a command, if it's a transition.
+    pub(crate) fn from_cmd_info(info: &ffi::RpsCmdDiagnosticInfo) -> Option<Self> {
+        if info.isTransition == 0 {
+            return None;
+        }
+
+        let transition = unsafe { &info.__bindgen_anon_1.transition };
+        Some(Self {
+            resource_index: transition.resourceIndex,
+            prev_access: AccessFlags::from_bits_truncate(transition.prevAccess.accessFlags as i32),
+            next_access: AccessFlags::from_bits_truncate(transition.nextAccess.accessFlags as i32),
+            range: transition.range,
+        })
+    }
+}
+
+/// Node command scheduled in a render graph.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeCmd<'a> {
+    /// Index of the command.
+    pub cmd_index: u32,
+    /// Name of the node declaration.
+    pub name: &'a str,
+    /// Properties of the node declaration.
+    pub flags: NodeFlags,
+}
+
+impl<'a> NodeCmd<'a> {
+    /// Returns the node of a node command from its raw information.
+    pub(crate) fn from_cmd_info(cmd_index: u32, info: &'a ffi::RpsCmdInfo) -> Result<Self, Error> {
+        let desc = unsafe { info.pNodeDesc.as_ref() }.ok_or(Error::InvalidData)?;
+        let name = match desc.name.is_null() {
+            true => "",
+            false => unsafe { CStr::from_ptr(desc.name) }
+                .to_str()
+                .map_err(|_| Error::InvalidData)?,
+        };
+
+        Ok(Self {
+            cmd_index,
+            name,
+            flags: NodeFlags::from_bits_truncate(desc.flags),
+        })
+    }
+}
+
+/// Backend recording the scheduled commands of a render graph, e.g. to a custom GPU abstraction.
+///
+/// [RenderGraph::record_batch_with] walks the commands of a batch and hands consecutive transitions to the backend as
+/// a single barrier batch, and consecutive node commands as a single command range. Nodes preferring to be executed
+/// as a render pass are recorded on their own, between [RuntimeBackend::begin_render_pass] and
+/// [RuntimeBackend::end_render_pass].
+pub trait RuntimeBackend {
+    /// Records a batch of resource transitions.
+    fn record_transitions(
+        &mut self,
+        cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        transitions: &[Transition],
+    ) -> Result<(), Error>;
+
+    /// Records a range of node commands.
+    ///
+    /// The default implementation records the commands with the render graph, invoking the bound node callbacks.
+    fn record_commands(
+        &mut self,
+        graph: &RenderGraph,
+        cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        frame_index: u64,
+        cmds: Range<u32>,
+    ) -> Result<(), Error> {
+        graph.record_commands(cmd_buffer, frame_index, cmds)
+    }
+
+    /// Begins the render pass of a node.
+    fn begin_render_pass(
+        &mut self,
+        _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        _node: &NodeCmd,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Ends the render pass of a node.
+    fn end_render_pass(
+        &mut self,
+        _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        _node: &NodeCmd,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Records the commands of a batch of the last update with a custom backend.
+    pub fn record_batch_with(
+        &self,
+        backend: &mut dyn RuntimeBackend,
+        batch: &CommandBatch,
+        cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+    ) -> Result<(), Error> {
+        // Copy the commands out of the diagnostic information first, backend calls may query it again.
+        let kinds = {
+            let graph = self.graph_ref();
+            let info = graph.raw_diagnostic_info()?;
+            diagnostics::raw_slice(&graph, info.pCmdDiagInfos, info.numCommandInfos)
+                .get(batch.cmds.start as usize..batch.cmds.end as usize)
+                .ok_or(Error::IndexOutOfBounds)?
+                .iter()
+                .map(|info| graph.cmd_kind(info))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut transitions = Vec::new();
+        let mut cmds = batch.cmds.start..batch.cmds.start;
+
+        for (cmd_index, kind) in batch.cmds.clone().zip(kinds) {
+            let (name, flags) = match kind {
+                CmdKind::Transition(transition) => {
+                    if !cmds.is_empty() {
+                        backend.record_commands(self, cmd_buffer, self.frame_index(), cmds)?;
+                    }
+                    cmds = cmd_index + 1..cmd_index + 1;
+
+                    transitions.push(transition);
+                    continue;
+                }
+                CmdKind::Node { name, flags, .. } => (name, flags),
+            };
+
+            if !transitions.is_empty() {
+                backend.record_transitions(cmd_buffer, &transitions)?;
+                transitions.clear();
+            }
+
+            if flags.contains(NodeFlags::PREFER_RENDER_PASS) {
+                if !cmds.is_empty() {
+                    backend.record_commands(self, cmd_buffer, self.frame_index(), cmds)?;
+                }
+
+                let node = NodeCmd {
+                    cmd_index,
+                    name: &name,
+                    flags,
+                };
+                backend.begin_render_pass(cmd_buffer, &node)?;
+                backend.record_commands(
+                    self,
+                    cmd_buffer,
+                    self.frame_index(),
+                    cmd_index..cmd_index + 1,
+                )?;
+                backend.end_render_pass(cmd_buffer, &node)?;
+
+                cmds = cmd_index + 1..cmd_index + 1;
+            } else {
+                cmds.end = cmd_index + 1;
+            }
+        }
+
+        if !transitions.is_empty() {
+            backend.record_transitions(cmd_buffer, &transitions)?;
+        }
+        if !cmds.is_empty() {
+            backend.record_commands(self, cmd_buffer, self.frame_index(), cmds)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Arc};
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, ResourceDesc},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    struct Dummy;
+    impl runtime::Callbacks for Dummy {}
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Transitions(usize),
+        Commands(Range<u32>),
+        BeginRenderPass(String),
+        EndRenderPass(String),
+    }
+
+    #[derive(Default)]
+    struct Backend {
+        events: Vec<Event>,
+    }
+
+    impl RuntimeBackend for Backend {
+        fn record_transitions(
+            &mut self,
+            _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+            transitions: &[Transition],
+        ) -> Result<(), Error> {
+            self.events.push(Event::Transitions(transitions.len()));
+            Ok(())
+        }
+
+        fn record_commands(
+            &mut self,
+            graph: &RenderGraph,
+            cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+            frame_index: u64,
+            cmds: Range<u32>,
+        ) -> Result<(), Error> {
+            self.events.push(Event::Commands(cmds.clone()));
+
+            // Querying the diagnostics again rebuilds them in the middle of the batch.
+            graph.diagnostics()?;
+            graph.record_commands(cmd_buffer, frame_index, cmds)
+        }
+
+        fn begin_render_pass(
+            &mut self,
+            _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+            node: &NodeCmd,
+        ) -> Result<(), Error> {
+            self.events
+                .push(Event::BeginRenderPass(node.name.to_string()));
+            Ok(())
+        }
+
+        fn end_render_pass(
+            &mut self,
+            _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+            node: &NodeCmd,
+        ) -> Result<(), Error> {
+            self.events
+                .push(Event::EndRenderPass(node.name.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record_with_backend() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "compute",
+            vec![ParamDesc::buffer("dst", AccessFlags::UNORDERED_ACCESS)],
+        ));
+        signature.nodes.push(NodeDesc {
+            flags: NodeFlags::GRAPHICS | NodeFlags::PREFER_RENDER_PASS,
+            ..NodeDesc::new(
+                "draw",
+                vec![ParamDesc::buffer("src", AccessFlags::VERTEX_BUFFER)],
+            )
+        });
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("vertices", 0, &ResourceDesc::buffer(1024))?;
+            builder.add_node("compute", &[&runtime::buffer_view(buffer)])?;
+            builder.add_node("draw", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+
+        let recorded = RefCell::new(Vec::new());
+        for name in ["compute", "draw"] {
+            let recorded = &recorded;
+            graph
+                .main_entry()
+                .bind_node(name, move |_| {
+                    recorded.borrow_mut().push(name);
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let mut backend = Backend::default();
+        for batch in &graph.batch_layout().unwrap() {
+            graph
+                .record_batch_with(&mut backend, batch, ffi::RpsRuntimeCommandBuffer::default())
+                .unwrap();
+        }
+
+        drop(graph);
+        assert_eq!(recorded.into_inner(), ["compute", "draw"]);
+
+        // The render pass node is recorded on its own, after the transition of the buffer to a vertex buffer.
+        let events = backend.events;
+        let begin = events
+            .iter()
+            .position(|event| *event == Event::BeginRenderPass("draw".to_string()))
+            .unwrap();
+        assert!(matches!(events[begin - 1], Event::Transitions(_)));
+        assert!(matches!(&events[begin + 1], Event::Commands(cmds) if cmds.len() == 1));
+        assert_eq!(events[begin + 2], Event::EndRenderPass("draw".to_string()));
+    }
+}
@@ -0,0 +1,296 @@
+//! Programmatic render graph construction.
+
+use std::{cell::Cell, ffi::CString, marker::PhantomData, mem, os::raw::c_void, ptr};
+
+use crate::{
+    guard,
+    runtime::{BufferView, ImageView, Rect, ResourceDesc, ResourceId, Viewport},
+    signature::EntrySignature,
+    Error,
+};
+use rps_sys as ffi;
+
+/// Value returned by RPS for invalid resource or node identifiers.
+const INDEX_NONE: u32 = u32::MAX;
+
+/// Node identifier within a render graph.
+pub type NodeId = ffi::RpsNodeId;
+
+/// Closure invoked on every render graph update to build the graph.
+pub type BuildCallback<'a> = dyn FnMut(&mut GraphBuilder) -> Result<(), Error> + 'a;
+
+/// Value that can be passed as a node argument.
+///
+/// Implemented for the resource views [ImageView] and [BufferView], and for all [DataArg] types.
+pub trait Arg {
+    /// Size of the value in bytes.
+    fn size(&self) -> usize;
+    /// Alignment of the value in bytes.
+    fn align(&self) -> usize;
+    /// Pointer to the value.
+    fn as_ptr(&self) -> *const c_void;
+    /// Returns whether the value is a resource view, which can only be passed to resource parameters.
+    fn is_resource(&self) -> bool;
+}
+
+/// Plain data type which can be passed as a data argument, e.g. [Viewport] or `[f32; 4]`.
+///
+/// # Safety
+///
+/// The type must be plain old data matching the layout of the parameter type on the C side, without pointers or
+/// references, as arguments are copied bytewise to the render graph.
+pub unsafe trait DataArg: Copy {}
+
+macro_rules! impl_data_arg {
+    ($($ty:ty),*) => {
+        $(unsafe impl DataArg for $ty {})*
+    };
+}
+
+impl_data_arg!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, Viewport, Rect);
+
+unsafe impl<T: DataArg, const N: usize> DataArg for [T; N] {}
+
+impl<T: DataArg> Arg for T {
+    fn size(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn align(&self) -> usize {
+        mem::align_of::<T>()
+    }
+
+    fn as_ptr(&self) -> *const c_void {
+        self as *const T as *const c_void
+    }
+
+    fn is_resource(&self) -> bool {
+        false
+    }
+}
+
+macro_rules! impl_resource_arg {
+    ($($ty:ty),*) => {
+        $(
+            impl Arg for $ty {
+                fn size(&self) -> usize {
+                    mem::size_of::<$ty>()
+                }
+
+                fn align(&self) -> usize {
+                    mem::align_of::<$ty>()
+                }
+
+                fn as_ptr(&self) -> *const c_void {
+                    self as *const $ty as *const c_void
+                }
+
+                fn is_resource(&self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+impl_resource_arg!(ImageView, BufferView);
+
+/// Render graph builder passed to the build closure.
+///
+/// Nodes can only be added from the declarations of the render graph [EntrySignature].
+pub struct GraphBuilder<'a> {
+    /// Raw handle.
+    handle: ffi::RpsRenderGraphBuilder,
+    /// Signature of the render graph main entry.
+    signature: &'a EntrySignature,
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    /// Returns the raw builder handle.
+    pub fn handle(&self) -> ffi::RpsRenderGraphBuilder {
+        self.handle
+    }
+
+    /// Returns the value of an entry parameter.
+    ///
+    /// Fails with [Error::TypeMismatch] if `T` doesn't match the size of the parameter, e.g. use `[T; N]` for array
+    /// parameters.
+    pub fn param<T: DataArg>(&self, index: usize) -> Result<&T, Error> {
+        let desc = self
+            .signature
+            .params
+            .get(index)
+            .ok_or(Error::IndexOutOfBounds)?;
+
+        if desc.is_resource() || desc.data_size() != mem::size_of::<T>() {
+            return Err(Error::TypeMismatch);
+        }
+
+        let ptr = unsafe { ffi::rpsRenderGraphGetParamVariable(self.handle, index as u32) };
+        if ptr.is_null() {
+            return Err(Error::InvalidOperation);
+        }
+
+        Ok(unsafe { &*(ptr as *const T) })
+    }
+
+    /// Returns the resource identifier of a resource entry parameter.
+    pub fn param_resource(&self, index: usize) -> Result<ResourceId, Error> {
+        let desc = self
+            .signature
+            .params
+            .get(index)
+            .ok_or(Error::IndexOutOfBounds)?;
+
+        if !desc.is_resource() {
+            return Err(Error::TypeMismatch);
+        }
+
+        let id = unsafe { ffi::rpsRenderGraphGetParamResourceId(self.handle, index as u32) };
+        match id {
+            INDEX_NONE => Err(Error::InvalidOperation),
+            id => Ok(id),
+        }
+    }
+
+    /// Declares a transient resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Debug name of the resource.
+    /// * `local_id` - Identifier of the resource unique within the build closure.
+    /// * `desc` - Resource description.
+    pub fn declare_resource(
+        &mut self,
+        name: &str,
+        local_id: u32,
+        desc: &ResourceDesc,
+    ) -> Result<ResourceId, Error> {
+        let name = CString::new(name).map_err(|_| Error::InvalidArguments)?;
+        let desc = self.alloc(&ffi::RpsResourceDesc::from(desc))?;
+
+        let id = unsafe {
+            ffi::rpsRenderGraphDeclareResource(self.handle, name.as_ptr(), local_id, desc)
+        };
+
+        match id {
+            INDEX_NONE => Err(Error::InvalidArguments),
+            id => Ok(id),
+        }
+    }
+
+    /// Adds a node to the render graph.
+    ///
+    /// Arguments are copied to the render graph, and must match the parameters of the node declaration: resource
+    /// parameters take resource views, data parameters take [DataArg] values of the parameter size. Other arguments
+    /// fail with [Error::TypeMismatch].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the node declaration in the render graph signature.
+    /// * `args` - Node arguments, one per declared parameter.
+    pub fn add_node(&mut self, name: &str, args: &[&dyn Arg]) -> Result<NodeId, Error> {
+        let signature = self.signature;
+        let decl_id = signature.node_index(name).ok_or(Error::UnknownNode)?;
+        let decl = &signature.nodes[decl_id];
+
+        if decl.params.len() != args.len() {
+            return Err(Error::InvalidArguments);
+        }
+
+        let mut vars = Vec::with_capacity(args.len());
+        for (param, arg) in decl.params.iter().zip(args) {
+            if param.is_resource() != arg.is_resource()
+                || usize::from(param.type_size) != arg.size()
+            {
+                return Err(Error::TypeMismatch);
+            }
+
+            vars.push(self.alloc(*arg)?);
+        }
+
+        let id = unsafe {
+            ffi::rpsRenderGraphAddNode(
+                self.handle,
+                decl_id as ffi::RpsNodeDeclId,
+                0,
+                None,
+                ptr::null_mut(),
+                0,
+                vars.as_ptr(),
+                vars.len() as u32,
+            )
+        };
+
+        match id {
+            INDEX_NONE => Err(Error::InvalidOperation),
+            id => Ok(id),
+        }
+    }
+
+    /// Copies a value to the render graph frame memory.
+    fn alloc(&mut self, arg: &dyn Arg) -> Result<ffi::RpsVariable, Error> {
+        let ptr =
+            unsafe { ffi::rpsRenderGraphAllocateDataAligned(self.handle, arg.size(), arg.align()) };
+
+        if ptr.is_null() {
+            return Err(Error::OutOfMemory);
+        }
+
+        unsafe { ptr::copy_nonoverlapping(arg.as_ptr() as *const u8, ptr as *mut u8, arg.size()) };
+
+        Ok(ptr)
+    }
+}
+
+/// Build closure and signature of the render graph being updated on the current thread.
+struct BuildContext<'a, 'b> {
+    callback: &'a mut BuildCallback<'b>,
+    signature: &'a EntrySignature,
+}
+
+thread_local! {
+    static BUILD_CONTEXT: Cell<*mut c_void> = Cell::new(ptr::null_mut());
+}
+
+/// Runs `f` with the build closure available to [build_render_graph].
+///
+/// RPS invokes the build callback synchronously from `rpsRenderGraphUpdate`, so the closure is only reachable for
+/// the duration of the call.
+pub(crate) fn with_build_callback<R>(
+    callback: &mut BuildCallback,
+    signature: &EntrySignature,
+    f: impl FnOnce() -> R,
+) -> R {
+    let mut context = BuildContext {
+        callback,
+        signature,
+    };
+
+    let prev = BUILD_CONTEXT.with(|cell| cell.replace(&mut context as *mut _ as *mut c_void));
+    let result = f();
+    BUILD_CONTEXT.with(|cell| cell.set(prev));
+
+    result
+}
+
+pub(crate) extern "C" fn build_render_graph(
+    builder: ffi::RpsRenderGraphBuilder,
+    _args: *const ffi::RpsConstant,
+    _num_args: u32,
+) -> ffi::RpsResult {
+    let context = BUILD_CONTEXT.with(|cell| cell.get()) as *mut BuildContext;
+    if context.is_null() {
+        return Error::InvalidOperation.into();
+    }
+
+    let context = unsafe { &mut *context };
+    let mut builder = GraphBuilder {
+        handle: builder,
+        signature: context.signature,
+        _marker: PhantomData,
+    };
+
+    guard::catch_result(|| (context.callback)(&mut builder))
+}
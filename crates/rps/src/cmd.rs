@@ -0,0 +1,355 @@
+//! Command callback context wrappers.
+
+use std::{mem, os::raw::c_void, ptr, slice, str};
+
+use crate::{
+    call,
+    runtime::{Format, Rect, RuntimeResource, Viewport},
+    Error,
+};
+use rps_sys as ffi;
+
+/// Render target setup of the command being recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderTargetInfo {
+    /// Number of MSAA samples of the render targets.
+    pub num_samples: u32,
+    /// Format of the depth stencil target, [Format::Unknown] if none is bound.
+    pub depth_stencil_format: Format,
+    /// Formats of the bound render targets.
+    pub render_target_formats: Vec<Format>,
+}
+
+/// Viewport setup of the command being recorded.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportInfo<'a> {
+    /// Default render area, derived from the bound render targets.
+    pub default_render_area: Rect,
+    /// Viewports of the command.
+    pub viewports: &'a [Viewport],
+    /// Scissor rectangles of the command.
+    pub scissor_rects: &'a [Rect],
+}
+
+/// Node arguments decoded from a [CmdContext].
+///
+/// Usually implemented with `#[derive(rps::NodeArgs)]`, which decodes one field per node parameter in declaration
+/// order and fails with [Error::TypeMismatch] if the fields don't match the node signature.
+///
+/// ```ignore
+/// #[derive(rps::NodeArgs)]
+/// struct DrawArgs {
+///     target: rps::runtime::ImageView,
+///     viewport: rps::runtime::Viewport,
+///     clear_color: [f32; 4],
+/// }
+///
+/// program.bind_node("draw", |context| {
+///     let args = DrawArgs::from_context(context)?;
+///     // ...
+///     Ok(())
+/// })?;
+/// ```
+pub trait NodeArgs<'a>: Sized {
+    /// Decodes the node arguments from a callback context.
+    fn from_context(context: &CmdContext<'a>) -> Result<Self, Error>;
+}
+
+/// Context of a node callback.
+///
+/// Wraps `ffi::RpsCmdCallbackContext` passed by RPS while recording a command. The context is only valid for the
+/// duration of the callback, so it can't escape it.
+pub struct CmdContext<'a> {
+    raw: &'a ffi::RpsCmdCallbackContext,
+}
+
+impl<'a> CmdContext<'a> {
+    /// Wraps a raw callback context.
+    ///
+    /// # Safety
+    ///
+    /// `context` must point to a valid callback context which outlives `'a`.
+    pub unsafe fn from_raw(context: *const ffi::RpsCmdCallbackContext) -> Self {
+        Self { raw: &*context }
+    }
+
+    /// Returns the raw callback context.
+    pub fn raw(&self) -> &'a ffi::RpsCmdCallbackContext {
+        self.raw
+    }
+
+    /// Returns the name of the node being recorded.
+    pub fn node_name(&self) -> Result<&'a str, Error> {
+        let mut name = ptr::null();
+        let mut len = 0;
+
+        call!(ffi::rpsCmdGetNodeName(self.raw, &mut name, &mut len))?;
+
+        if name.is_null() {
+            return Ok("");
+        }
+
+        let bytes = unsafe { slice::from_raw_parts(name as *const u8, len) };
+        str::from_utf8(bytes).map_err(|_| Error::InvalidData)
+    }
+
+    /// Returns the runtime command buffer to record the commands to.
+    #[inline]
+    pub fn command_buffer(&self) -> ffi::RpsRuntimeCommandBuffer {
+        self.raw.hCommandBuffer
+    }
+
+    /// Returns the user context passed to the record call.
+    #[inline]
+    pub fn user_record_context(&self) -> *mut c_void {
+        self.raw.pUserRecordContext
+    }
+
+    /// Returns the user tag of the node.
+    #[inline]
+    pub fn user_tag(&self) -> u32 {
+        self.raw.userTag
+    }
+
+    /// Returns the number of node arguments.
+    #[inline]
+    pub fn num_args(&self) -> usize {
+        self.raw.numArgs as usize
+    }
+
+    /// Returns the description of a node parameter.
+    pub fn param_desc(&self, index: usize) -> Result<ffi::RpsParameterDesc, Error> {
+        let mut desc = ffi::RpsParameterDesc::default();
+        call!(ffi::rpsCmdGetParamDesc(self.raw, index as u32, &mut desc))?;
+
+        Ok(desc)
+    }
+
+    /// Returns a node argument.
+    ///
+    /// Fails with [Error::TypeMismatch] if `T` doesn't match the size of the parameter type, or requires a stricter
+    /// alignment than the argument has.
+    pub fn arg<T: Copy>(&self, index: usize) -> Result<&'a T, Error> {
+        self.arg_array(index)
+            .and_then(|array| array.first().ok_or(Error::InvalidData))
+    }
+
+    /// Returns the elements of an array node argument.
+    ///
+    /// Non-array arguments are returned as a single element slice. Fails like [CmdContext::arg].
+    pub fn arg_array<T: Copy>(&self, index: usize) -> Result<&'a [T], Error> {
+        if index >= self.num_args() {
+            return Err(Error::IndexOutOfBounds);
+        }
+
+        let desc = self.param_desc(index)?;
+        if usize::from(desc.typeInfo.size) != mem::size_of::<T>() {
+            return Err(Error::TypeMismatch);
+        }
+
+        let ptr = unsafe { *self.raw.ppArgs.add(index) } as *const T;
+        if ptr.is_null() {
+            return Ok(&[]);
+        }
+
+        // RPS allocates arguments with the alignment of the parameter type, which may be less than `T` requires.
+        if ptr as usize % mem::align_of::<T>() != 0 {
+            return Err(Error::TypeMismatch);
+        }
+
+        let len = desc.arraySize.max(1) as usize;
+        Ok(unsafe { slice::from_raw_parts(ptr, len) })
+    }
+
+    /// Returns the runtime resources bound to a resource view argument, one per array element.
+    pub fn arg_resources(&self, index: usize) -> Result<Vec<RuntimeResource>, Error> {
+        if index >= self.num_args() {
+            return Err(Error::IndexOutOfBounds);
+        }
+
+        let len = self.param_desc(index)?.arraySize.max(1);
+        let mut resources = vec![RuntimeResource::default(); len as usize];
+
+        call!(ffi::rpsCmdGetArgResources(
+            self.raw,
+            index as u32,
+            0,
+            resources.as_mut_ptr(),
+            len as usize
+        ))?;
+
+        Ok(resources)
+    }
+
+    /// Returns the render target setup of the command.
+    pub fn render_targets(&self) -> Result<RenderTargetInfo, Error> {
+        let mut info = ffi::RpsCmdRenderTargetInfo::default();
+        call!(ffi::rpsCmdGetRenderTargetsInfo(self.raw, &mut info))?;
+
+        let render_target_formats = info.renderTargetFormats[..info.numRenderTargets as usize]
+            .iter()
+            .map(|format| Format::try_from(*format))
+            .collect::<Result<_, _>>()?;
+
+        Ok(RenderTargetInfo {
+            num_samples: info.numSamples,
+            depth_stencil_format: Format::try_from(info.depthStencilFormat)?,
+            render_target_formats,
+        })
+    }
+
+    /// Returns the viewport setup of the command.
+    pub fn viewports(&self) -> Result<ViewportInfo<'a>, Error> {
+        let mut info = ffi::RpsCmdViewportInfo::default();
+        call!(ffi::rpsCmdGetViewportInfo(self.raw, &mut info))?;
+
+        let viewports = match info.numViewports {
+            0 => &[],
+            num => unsafe { slice::from_raw_parts(info.pViewports, num as usize) },
+        };
+
+        let scissor_rects = match info.numScissorRects {
+            0 => &[],
+            num => unsafe { slice::from_raw_parts(info.pScissorRects, num as usize) },
+        };
+
+        Ok(ViewportInfo {
+            default_render_area: info.defaultRenderArea,
+            viewports,
+            scissor_rects,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Arc};
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        guard,
+        runtime::{self, AccessFlags, ImageView, ResourceDesc},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    struct Dummy;
+    impl runtime::Callbacks for Dummy {}
+
+    #[derive(crate::NodeArgs)]
+    struct ClearArgs {
+        target: ImageView,
+        color: [f32; 4],
+    }
+
+    #[derive(crate::NodeArgs)]
+    struct WrongArgs(ImageView, u64);
+
+    #[test]
+    fn derive_node_args() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "clear",
+            vec![
+                ParamDesc::image("target", AccessFlags::RENDER_TARGET | AccessFlags::CLEAR),
+                ParamDesc::new::<[f32; 4]>("color"),
+            ],
+        ));
+
+        let decoded = RefCell::new(Vec::new());
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            let desc = ResourceDesc::image_2d(Format::R8G8B8A8Unorm, 64, 64);
+            let target = builder.declare_resource("target", 0, &desc)?;
+            builder.add_node(
+                "clear",
+                &[&runtime::image_view(target), &[0.0_f32, 0.5, 1.0, 1.0]],
+            )?;
+            Ok(())
+        });
+
+        graph
+            .main_entry()
+            .bind_node("clear", |context| {
+                assert!(matches!(
+                    WrongArgs::from_context(context),
+                    Err(Error::TypeMismatch)
+                ));
+
+                let args = ClearArgs::from_context(context)?;
+                decoded
+                    .borrow_mut()
+                    .push((args.target.base.resourceId, args.color));
+                Ok(())
+            })
+            .unwrap();
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        for batch in &graph.batch_layout().unwrap() {
+            graph
+                .record_batch(batch, ffi::RpsRuntimeCommandBuffer::default())
+                .unwrap();
+        }
+
+        drop(graph);
+        assert_eq!(decoded.into_inner(), [(0, [0.0, 0.5, 1.0, 1.0])]);
+    }
+
+    #[test]
+    fn nested_call_after_callback_error() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "clear",
+            vec![ParamDesc::image(
+                "target",
+                AccessFlags::RENDER_TARGET | AccessFlags::CLEAR,
+            )],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let desc = ResourceDesc::image_2d(Format::R8G8B8A8Unorm, 64, 64);
+            let target = builder.declare_resource("target", 0, &desc)?;
+            builder.add_node("clear", &[&runtime::image_view(target)])?;
+            Ok(())
+        });
+
+        let names = RefCell::new(Vec::new());
+        graph
+            .main_entry()
+            .bind_node("clear", |context| {
+                // A runtime callback invoked by the record call already failed.
+                guard::catch_result(|| Err(Error::callback("runtime callback failed")));
+
+                // Calls made by the node callback don't pick up the error of the record call.
+                names.borrow_mut().push(context.node_name()?.to_string());
+                Ok(())
+            })
+            .unwrap();
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let layout = graph.batch_layout().unwrap();
+        let batch = &layout.batches[0];
+        assert!(matches!(
+            graph.record_batch(batch, ffi::RpsRuntimeCommandBuffer::default()),
+            Err(Error::Callback(_))
+        ));
+        assert!(guard::take().is_none());
+
+        drop(graph);
+        assert_eq!(names.into_inner(), ["clear"]);
+    }
+}
@@ -0,0 +1,481 @@
+//! Diagnostic information of render graphs.
+
+mod chrome;
+mod dot;
+mod heaps;
+
+pub use heaps::{HeapAllocation, HeapOccupancy};
+
+use std::{ffi::CStr, marker::PhantomData, ops::Range, os::raw::c_char, slice};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{NodeCmd, SubresourceRange, Transition},
+    call,
+    graph::{BatchLayout, RenderGraph},
+    runtime::{
+        AccessFlags, HeapPlacement, MemoryRequirement, ResourceDesc, RuntimeHeap, RuntimeResource,
+    },
+    signature::NodeFlags,
+    Error,
+};
+use rps_sys as ffi;
+
+const INDEX_NONE: u32 = u32::MAX;
+
+/// Diagnostic information of a resource.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourceInfo {
+    /// Name of the resource.
+    pub name: String,
+    /// Index of the temporal layer for a temporal resource, 0 otherwise.
+    pub temporal_layer: u32,
+    /// Whether the resource is imported by the application.
+    pub is_external: bool,
+    /// Resource description.
+    pub desc: ResourceDesc,
+    /// Union of all accesses to the resource.
+    pub all_accesses: AccessFlags,
+    /// Initial access of the resource.
+    pub initial_access: AccessFlags,
+    /// Range of the command indices the resource is alive for, `None` if it's never accessed.
+    pub lifetime: Option<Range<u32>>,
+    /// Memory requirement of the resource.
+    pub memory_requirement: MemoryRequirement,
+    /// Placement of the resource in a heap.
+    pub placement: HeapPlacement,
+    /// Runtime resource, null if the resource isn't created.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub runtime_resource: RuntimeResource,
+}
+
+/// Access of a resource by a node command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourceAccess {
+    /// Index of the resource in the render graph.
+    pub resource_index: u32,
+    /// Access of the resource.
+    pub access: AccessFlags,
+    /// Subresources accessed.
+    #[cfg_attr(feature = "serde", serde(with = "crate::backend::SubresourceRangeDef"))]
+    pub range: SubresourceRange,
+}
+
+/// Kind of a scheduled command.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CmdKind {
+    /// Node command.
+    Node {
+        /// Name of the node declaration.
+        name: String,
+        /// Properties of the node declaration.
+        flags: NodeFlags,
+        /// Resources accessed by the node.
+        accesses: Vec<ResourceAccess>,
+    },
+    /// Resource transition.
+    Transition(Transition),
+}
+
+/// Diagnostic information of a scheduled command.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CmdInfo {
+    /// Index of the command.
+    pub cmd_index: u32,
+    /// Index of the queue the command is submitted to, `None` if it isn't part of a batch.
+    pub queue_index: Option<u32>,
+    /// Kind of the command.
+    pub kind: CmdKind,
+}
+
+/// Diagnostic information of a heap.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeapInfo {
+    /// Size in bytes.
+    pub size: u64,
+    /// Size in bytes used by the last update.
+    pub used_size: u64,
+    /// Maximum size in bytes used by any update.
+    pub max_used_size: u64,
+    /// Alignment in bytes.
+    pub alignment: u32,
+    /// Index of the memory type.
+    pub memory_type_index: u32,
+    /// Runtime heap, null if the heap isn't created.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub runtime_heap: RuntimeHeap,
+}
+
+/// Diagnostic information of a render graph, as of its last update.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostics {
+    /// Resources, indexed by resource index.
+    pub resources: Vec<ResourceInfo>,
+    /// Scheduled commands, indexed by command index.
+    pub cmds: Vec<CmdInfo>,
+    /// Heaps.
+    pub heaps: Vec<HeapInfo>,
+}
+
+impl TryFrom<&ffi::RpsResourceDiagnosticInfo> for ResourceInfo {
+    type Error = Error;
+
+    fn try_from(value: &ffi::RpsResourceDiagnosticInfo) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: string(value.name)?,
+            temporal_layer: value.temporalChildIndex,
+            is_external: value.isExternal != 0,
+            desc: ResourceDesc::try_from(&value.desc)?,
+            all_accesses: AccessFlags::from_bits_truncate(value.allAccesses.accessFlags as i32),
+            initial_access: AccessFlags::from_bits_truncate(value.initialAccess.accessFlags as i32),
+            lifetime: match value.lifetimeBegin {
+                INDEX_NONE => None,
+                // The end of the lifetime is the index of the last command accessing the resource.
+                begin => Some(begin..value.lifetimeEnd.saturating_add(1)),
+            },
+            memory_requirement: MemoryRequirement::from(&value.allocRequirement),
+            placement: HeapPlacement {
+                heap: value.allocPlacement.hHeap,
+                offset: value.allocPlacement.offset,
+            },
+            runtime_resource: value.hRuntimeHandle,
+        })
+    }
+}
+
+impl From<&ffi::RpsCmdAccessInfo> for ResourceAccess {
+    fn from(value: &ffi::RpsCmdAccessInfo) -> Self {
+        Self {
+            resource_index: value.resourceId,
+            access: AccessFlags::from_bits_truncate(value.access.accessFlags as i32),
+            range: value.range,
+        }
+    }
+}
+
+impl From<&ffi::RpsHeapDiagnosticInfo> for HeapInfo {
+    fn from(value: &ffi::RpsHeapDiagnosticInfo) -> Self {
+        Self {
+            size: value.size,
+            used_size: value.usedSize,
+            max_used_size: value.maxUsedSize,
+            alignment: value.alignment,
+            memory_type_index: value.memoryTypeIndex,
+            runtime_heap: value.hRuntimeHeap,
+        }
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Returns the diagnostic information of the last update.
+    pub fn diagnostics(&self) -> Result<Diagnostics, Error> {
+        self.graph_ref().diagnostics()
+    }
+
+    /// Returns the handle borrowed for queries of the render graph state.
+    pub(crate) fn graph_ref(&self) -> GraphRef<'_> {
+        GraphRef::new(self.handle())
+    }
+}
+
+/// Render graph handle borrowed for queries of its state, shared by [RenderGraph] and [crate::phase::PhaseGraph].
+#[derive(Clone, Copy)]
+pub(crate) struct GraphRef<'a> {
+    handle: ffi::RpsRenderGraph,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> GraphRef<'a> {
+    pub(crate) fn new(handle: ffi::RpsRenderGraph) -> Self {
+        Self {
+            handle,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn handle(&self) -> ffi::RpsRenderGraph {
+        self.handle
+    }
+
+    /// Returns the command batch layout of the last update.
+    pub(crate) fn batch_layout(&self) -> Result<BatchLayout, Error> {
+        let mut layout = ffi::RpsRenderGraphBatchLayout::default();
+        call!(ffi::rpsRenderGraphGetBatchLayout(self.handle, &mut layout))?;
+
+        Ok(BatchLayout::from(&layout))
+    }
+
+    /// Returns the diagnostic information of the last update.
+    pub(crate) fn diagnostics(&self) -> Result<Diagnostics, Error> {
+        let layout = self.batch_layout()?;
+        let info = self.raw_diagnostic_info()?;
+
+        let resources = raw_slice(self, info.pResourceDiagInfos, info.numResourceInfos)
+            .iter()
+            .map(ResourceInfo::try_from)
+            .collect::<Result<_, _>>()?;
+
+        let cmds = raw_slice(self, info.pCmdDiagInfos, info.numCommandInfos)
+            .iter()
+            .map(|cmd| {
+                Ok(CmdInfo {
+                    cmd_index: cmd.cmdIndex,
+                    queue_index: layout
+                        .iter()
+                        .find(|batch| batch.cmds.contains(&cmd.cmdIndex))
+                        .map(|batch| batch.queue_index),
+                    kind: self.cmd_kind(cmd)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let heaps = raw_slice(self, info.pHeapDiagInfos, info.numHeapInfos)
+            .iter()
+            .map(HeapInfo::from)
+            .collect();
+
+        Ok(Diagnostics {
+            resources,
+            cmds,
+            heaps,
+        })
+    }
+
+    /// Returns the kind of a scheduled command, copied out of its diagnostic information.
+    pub(crate) fn cmd_kind(&self, info: &ffi::RpsCmdDiagnosticInfo) -> Result<CmdKind, Error> {
+        if let Some(transition) = Transition::from_cmd_info(info) {
+            return Ok(CmdKind::Transition(transition));
+        }
+
+        let cmd = self.raw_cmd_info(info.cmdIndex)?;
+        let node = NodeCmd::from_cmd_info(info.cmdIndex, &cmd)?;
+        Ok(CmdKind::Node {
+            name: node.name.to_string(),
+            flags: node.flags,
+            accesses: raw_slice(self, cmd.pCmdAccessInfos, cmd.numAccesses)
+                .iter()
+                .map(ResourceAccess::from)
+                .collect(),
+        })
+    }
+
+    /// Returns the raw diagnostic information, owned by the render graph.
+    ///
+    /// RPS rebuilds the information on every query, so the arrays it points to are only valid until the next one.
+    pub(crate) fn raw_diagnostic_info(&self) -> Result<ffi::RpsRenderGraphDiagnosticInfo, Error> {
+        let mut info = ffi::RpsRenderGraphDiagnosticInfo::default();
+        call!(ffi::rpsRenderGraphGetDiagnosticInfo(
+            self.handle,
+            &mut info,
+            ffi::RpsRenderGraphDiagnosticInfoFlagBits_RPS_RENDERGRAPH_DIAGNOSTIC_INFO_DEFAULT
+        ))?;
+
+        Ok(info)
+    }
+
+    /// Returns the raw information of a node command, owned by the render graph.
+    pub(crate) fn raw_cmd_info(&self, cmd_index: u32) -> Result<ffi::RpsCmdInfo, Error> {
+        let mut info = ffi::RpsCmdInfo::default();
+        call!(ffi::rpsRenderGraphGetCmdInfo(
+            self.handle,
+            cmd_index,
+            &mut info
+        ))?;
+
+        Ok(info)
+    }
+}
+
+/// Returns a raw array owned by `graph`, e.g. of the diagnostic information, see [GraphRef::raw_diagnostic_info].
+///
+/// The borrow of `graph` doesn't prevent the next query, so copy the contents out before calling into RPS again.
+pub(crate) fn raw_slice<'a, T>(_graph: &'a GraphRef<'_>, ptr: *const T, len: u32) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        return &[];
+    }
+
+    unsafe { slice::from_raw_parts(ptr, len as usize) }
+}
+
+fn string(ptr: *const c_char) -> Result<String, Error> {
+    if ptr.is_null() {
+        return Ok(String::new());
+    }
+
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| Error::InvalidData)
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+
+    /// Returns a buffer placed at `bytes` of `heap`, or dedicated for a null heap, alive for the commands `cmds`.
+    pub(crate) fn resource(
+        name: &str,
+        heap: RuntimeHeap,
+        bytes: Range<u64>,
+        cmds: Range<u32>,
+    ) -> ResourceInfo {
+        ResourceInfo {
+            name: name.to_string(),
+            temporal_layer: 0,
+            is_external: false,
+            desc: ResourceDesc::buffer(bytes.end - bytes.start),
+            all_accesses: AccessFlags::UNORDERED_ACCESS,
+            initial_access: AccessFlags::UNORDERED_ACCESS,
+            lifetime: Some(cmds),
+            memory_requirement: MemoryRequirement {
+                size: bytes.end - bytes.start,
+                alignment: 256,
+                memory_type_index: 0,
+            },
+            placement: HeapPlacement {
+                heap,
+                offset: bytes.start,
+            },
+            runtime_resource: RuntimeResource::default(),
+        }
+    }
+
+    /// Returns a node command accessing the whole of the given resources.
+    pub(crate) fn node(
+        cmd_index: u32,
+        queue_index: Option<u32>,
+        name: &str,
+        accesses: &[(u32, AccessFlags)],
+    ) -> CmdInfo {
+        CmdInfo {
+            cmd_index,
+            queue_index,
+            kind: CmdKind::Node {
+                name: name.to_string(),
+                flags: NodeFlags::empty(),
+                accesses: accesses
+                    .iter()
+                    .map(|&(resource_index, access)| ResourceAccess {
+                        resource_index,
+                        access,
+                        range: Default::default(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// Returns a fully used heap of `size` bytes.
+    pub(crate) fn heap(runtime_heap: RuntimeHeap, size: u64) -> HeapInfo {
+        HeapInfo {
+            size,
+            used_size: size,
+            max_used_size: size,
+            alignment: 256,
+            memory_type_index: 0,
+            runtime_heap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, HostRuntime},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    #[test]
+    fn render_graph_diagnostics() {
+        let device = DeviceBuilder::new()
+            .build_null(Arc::new(HostRuntime::new()))
+            .unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "fill",
+            vec![ParamDesc::buffer("dst", AccessFlags::UNORDERED_ACCESS)],
+        ));
+        signature.nodes.push(NodeDesc::new(
+            "read",
+            vec![ParamDesc::buffer("src", AccessFlags::SHADER_RESOURCE)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature.clone()))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("data", 0, &ResourceDesc::buffer(256))?;
+            builder.add_node("fill", &[&runtime::buffer_view(buffer)])?;
+            builder.add_node("read", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let diagnostics = graph.diagnostics().unwrap();
+
+        let resource = &diagnostics.resources[0];
+        assert_eq!(resource.name, "data");
+        assert_eq!(resource.desc, ResourceDesc::buffer(256));
+        assert!(resource
+            .all_accesses
+            .contains(AccessFlags::UNORDERED_ACCESS | AccessFlags::SHADER_RESOURCE));
+        assert!(resource.lifetime.is_some());
+        assert!(!resource.runtime_resource.ptr.is_null());
+
+        let nodes = diagnostics
+            .cmds
+            .iter()
+            .filter_map(|cmd| match &cmd.kind {
+                CmdKind::Node { name, .. } => Some(name.as_str()),
+                CmdKind::Transition(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(nodes, ["fill", "read"]);
+        assert!(diagnostics.cmds.iter().any(|cmd| matches!(
+            &cmd.kind,
+            CmdKind::Node { name, accesses, .. } if name == "fill"
+                && accesses.iter().any(|access| access.resource_index == 0
+                    && access.access.contains(AccessFlags::UNORDERED_ACCESS))
+        )));
+        assert!(diagnostics
+            .cmds
+            .iter()
+            .any(|cmd| matches!(&cmd.kind, CmdKind::Transition(transition) if transition.resource_index == 0)));
+
+        for (index, cmd) in diagnostics.cmds.iter().enumerate() {
+            assert_eq!(cmd.cmd_index as usize, index);
+        }
+
+        // Runtime handles aren't serialized, everything else survives a JSON roundtrip.
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&diagnostics).unwrap();
+            let loaded = serde_json::from_str::<Diagnostics>(&json).unwrap();
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+            assert!(loaded.resources[0].runtime_resource.ptr.is_null());
+
+            // Signatures survive a roundtrip as well, e.g. to load them from a file.
+            let json = serde_json::to_string(&signature).unwrap();
+            let loaded = serde_json::from_str::<EntrySignature>(&json).unwrap();
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+            assert_eq!(
+                loaded.node("fill").unwrap().params[0].access,
+                AccessFlags::UNORDERED_ACCESS
+            );
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Chrome trace event export of scheduled render graphs.
+
+use std::fmt::Write;
+
+use super::{CmdKind, Diagnostics};
+use crate::{graph::RenderGraph, Error};
+
+/// Process of the queue tracks.
+const QUEUES_PID: u32 = 1;
+/// Process of the first heap, followed by the other heaps and the dedicated resources.
+const HEAPS_PID: u32 = 2;
+/// Track of the commands which aren't part of a batch.
+const UNBATCHED_TID: u32 = u32::MAX;
+
+impl Diagnostics {
+    /// Formats the schedule as Chrome trace event JSON, for `chrome://tracing` or Perfetto.
+    ///
+    /// The timeline is measured in command indices, one microsecond per command. Every queue gets a track with its
+    /// commands as slices, commands outside of any batch share an additional track. Every heap gets a process with a
+    /// track per resource placed in it, labeled with the offset, as aliased resources overlap in time. Resources with
+    /// a dedicated allocation share an additional process after the heaps.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+
+        push_metadata(&mut events, QUEUES_PID, None, "process_name", "Queues");
+
+        let mut queues = self
+            .cmds
+            .iter()
+            .map(|cmd| cmd.queue_index.unwrap_or(UNBATCHED_TID))
+            .collect::<Vec<_>>();
+        queues.sort_unstable();
+        queues.dedup();
+        for tid in queues {
+            let name = match tid {
+                UNBATCHED_TID => "unbatched".to_string(),
+                queue_index => format!("queue {}", queue_index),
+            };
+            push_metadata(&mut events, QUEUES_PID, Some(tid), "thread_name", &name);
+        }
+
+        for cmd in &self.cmds {
+            let (name, category) = match &cmd.kind {
+                CmdKind::Node { name, .. } => (name.as_str(), "node"),
+                CmdKind::Transition(_) => ("transition", "transition"),
+            };
+
+            let mut event = String::new();
+            let _ = write!(
+                event,
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":{},\"tid\":{},\"ts\":{},\"dur\":1,\"args\":{{\"cmd_index\":{}",
+                escape(name),
+                category,
+                QUEUES_PID,
+                cmd.queue_index.unwrap_or(UNBATCHED_TID),
+                cmd.cmd_index,
+                cmd.cmd_index
+            );
+            if let CmdKind::Transition(transition) = &cmd.kind {
+                let _ = write!(
+                    event,
+                    ",\"resource_index\":{},\"prev_access\":\"{:?}\",\"next_access\":\"{:?}\"",
+                    transition.resource_index, transition.prev_access, transition.next_access
+                );
+            }
+            event.push_str("}}");
+            events.push(event);
+        }
+
+        let dedicated = HEAPS_PID + self.heaps.len() as u32;
+        for (index, heap) in self.heaps.iter().enumerate() {
+            let name = format!("heap {} ({} bytes)", index, heap.size);
+            push_metadata(
+                &mut events,
+                HEAPS_PID + index as u32,
+                None,
+                "process_name",
+                &name,
+            );
+        }
+        push_metadata(&mut events, dedicated, None, "process_name", "dedicated");
+
+        for (index, resource) in self.resources.iter().enumerate() {
+            let lifetime = match &resource.lifetime {
+                Some(lifetime) => lifetime,
+                None => continue,
+            };
+
+            let heap = match resource.placement.heap.ptr.is_null() {
+                true => None,
+                false => self
+                    .heaps
+                    .iter()
+                    .position(|heap| heap.runtime_heap.ptr == resource.placement.heap.ptr),
+            };
+            let (pid, track) = match heap {
+                Some(heap) => (
+                    HEAPS_PID + heap as u32,
+                    format!(
+                        "heap {} @ {}: {}",
+                        heap, resource.placement.offset, resource.name
+                    ),
+                ),
+                None => (dedicated, format!("dedicated: {}", resource.name)),
+            };
+
+            // Every resource gets its own track, slices of aliased resources overlap in time.
+            let tid = index as u32;
+            push_metadata(&mut events, pid, Some(tid), "thread_name", &track);
+
+            let mut event = String::new();
+            let _ = write!(
+                event,
+                "{{\"name\":\"{}\",\"cat\":\"resource\",\"ph\":\"X\",\"pid\":{},\"tid\":{},\"ts\":{},\"dur\":{},\"args\":{{\"resource_index\":{},\"offset\":{},\"size\":{}}}}}",
+                escape(&resource.name),
+                pid,
+                tid,
+                lifetime.start,
+                lifetime.len(),
+                index,
+                resource.placement.offset,
+                resource.memory_requirement.size
+            );
+            events.push(event);
+        }
+
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Formats the schedule of the last update as Chrome trace event JSON, see [Diagnostics::to_chrome_trace].
+    pub fn to_chrome_trace(&self) -> Result<String, Error> {
+        Ok(self.diagnostics()?.to_chrome_trace())
+    }
+}
+
+fn push_metadata(events: &mut Vec<String>, pid: u32, tid: Option<u32>, kind: &str, name: &str) {
+    let mut event = String::new();
+    let _ = write!(
+        event,
+        "{{\"name\":\"{}\",\"ph\":\"M\",\"pid\":{}",
+        kind, pid
+    );
+    if let Some(tid) = tid {
+        let _ = write!(event, ",\"tid\":{}", tid);
+    }
+    let _ = write!(event, ",\"args\":{{\"name\":\"{}\"}}}}", escape(name));
+
+    events.push(event);
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_void;
+
+    use super::*;
+    use crate::{
+        diagnostics::test_util::{heap, node, resource},
+        runtime::RuntimeHeap,
+    };
+
+    #[test]
+    fn schedule_to_chrome_trace() {
+        let runtime_heap = RuntimeHeap {
+            ptr: 1 as *mut c_void,
+        };
+
+        let diagnostics = Diagnostics {
+            resources: vec![
+                resource("shadow", runtime_heap, 0..256, 0..1),
+                resource("bloom", runtime_heap, 0..256, 2..3),
+                resource("blur", runtime_heap, 128..384, 0..3),
+                resource("\"back\"", RuntimeHeap::default(), 0..256, 0..3),
+            ],
+            cmds: vec![
+                node(0, Some(0), "shadows", &[]),
+                node(1, Some(1), "async", &[]),
+                node(2, Some(0), "bloom", &[]),
+                node(3, None, "present", &[]),
+            ],
+            heaps: vec![heap(runtime_heap, 512)],
+        };
+
+        let trace = diagnostics.to_chrome_trace();
+        assert!(trace.starts_with("{\"traceEvents\":["));
+        assert!(trace.contains("\"args\":{\"name\":\"queue 1\"}"));
+        assert!(trace.contains("\"args\":{\"name\":\"heap 0 (512 bytes)\"}"));
+        assert!(trace.contains(
+            "{\"name\":\"async\",\"cat\":\"node\",\"ph\":\"X\",\"pid\":1,\"tid\":1,\"ts\":1,\"dur\":1"
+        ));
+
+        // Commands outside of any batch get a named track as well.
+        assert!(trace.contains(&format!(
+            "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"unbatched\"}}}}",
+            u32::MAX
+        )));
+        assert!(trace.contains(&format!(
+            "{{\"name\":\"present\",\"cat\":\"node\",\"ph\":\"X\",\"pid\":1,\"tid\":{}",
+            u32::MAX
+        )));
+
+        // Every resource gets its own track in the process of its heap, so overlapping lifetimes don't nest.
+        assert!(trace.contains(
+            "{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":2,\"tid\":2,\"args\":{\"name\":\"heap 0 @ 128: blur\"}}"
+        ));
+        assert!(trace.contains(
+            "{\"name\":\"shadow\",\"cat\":\"resource\",\"ph\":\"X\",\"pid\":2,\"tid\":0,\"ts\":0,\"dur\":1"
+        ));
+        assert!(trace.contains(
+            "{\"name\":\"bloom\",\"cat\":\"resource\",\"ph\":\"X\",\"pid\":2,\"tid\":1,\"ts\":2,\"dur\":1"
+        ));
+        assert!(trace.contains(
+            "{\"name\":\"blur\",\"cat\":\"resource\",\"ph\":\"X\",\"pid\":2,\"tid\":2,\"ts\":0,\"dur\":3"
+        ));
+
+        // Dedicated resources go to the process after the heaps.
+        assert!(trace.contains(
+            "{\"name\":\"process_name\",\"ph\":\"M\",\"pid\":3,\"args\":{\"name\":\"dedicated\"}}"
+        ));
+        assert!(trace.contains(
+            "{\"name\":\"\\\"back\\\"\",\"cat\":\"resource\",\"ph\":\"X\",\"pid\":3,\"tid\":3"
+        ));
+
+        serde_json::from_str::<serde_json::Value>(&trace).unwrap();
+    }
+}
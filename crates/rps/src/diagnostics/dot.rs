@@ -0,0 +1,178 @@
+//! Graphviz DOT export of scheduled render graphs.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use super::{CmdInfo, CmdKind, Diagnostics};
+use crate::{graph::RenderGraph, runtime::AccessFlags, Error};
+
+/// Accesses drawn as edges from the node to the resource.
+const WRITE_ACCESS: AccessFlags = AccessFlags::from_bits_truncate(
+    AccessFlags::UNORDERED_ACCESS.bits()
+        | AccessFlags::RENDER_TARGET.bits()
+        | AccessFlags::DEPTH_WRITE.bits()
+        | AccessFlags::STENCIL_WRITE.bits()
+        | AccessFlags::STREAM_OUT.bits()
+        | AccessFlags::COPY_DEST.bits()
+        | AccessFlags::RESOLVE_DEST.bits()
+        | AccessFlags::RAYTRACING_AS_BUILD.bits()
+        | AccessFlags::CPU_WRITE.bits(),
+);
+
+impl Diagnostics {
+    /// Formats the schedule as a Graphviz DOT graph.
+    ///
+    /// Commands are laid out in scheduled order, with a cluster per queue. Every node is connected to the resources it
+    /// accesses, from the resource for reads and to the resource for writes, labeled with the access. Every transition
+    /// is connected to the resource it transitions, labeled with the accesses before and after it.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from(
+            "digraph RenderGraph {\n    rankdir=LR;\n    node [fontname=\"monospace\"];\n",
+        );
+
+        let mut lanes = BTreeMap::<Option<u32>, Vec<&CmdInfo>>::new();
+        for cmd in &self.cmds {
+            lanes.entry(cmd.queue_index).or_default().push(cmd);
+        }
+
+        for (queue_index, cmds) in &lanes {
+            let indent = match queue_index {
+                Some(queue_index) => {
+                    let _ = writeln!(dot, "    subgraph cluster_queue{} {{", queue_index);
+                    let _ = writeln!(dot, "        label=\"queue {}\";", queue_index);
+                    "        "
+                }
+                None => "    ",
+            };
+
+            for cmd in cmds {
+                let (label, shape) = match &cmd.kind {
+                    CmdKind::Node { name, .. } => (escape(name), "box"),
+                    CmdKind::Transition(_) => ("transition".to_string(), "diamond"),
+                };
+                let _ = writeln!(
+                    dot,
+                    "{}cmd{} [label=\"{}: {}\", shape={}];",
+                    indent, cmd.cmd_index, cmd.cmd_index, label, shape
+                );
+            }
+
+            for pair in cmds.windows(2) {
+                let _ = writeln!(
+                    dot,
+                    "{}cmd{} -> cmd{};",
+                    indent, pair[0].cmd_index, pair[1].cmd_index
+                );
+            }
+
+            if queue_index.is_some() {
+                dot.push_str("    }\n");
+            }
+        }
+
+        for (index, resource) in self.resources.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    res{} [label=\"{}\", shape=note];",
+                index,
+                escape(&resource.name)
+            );
+        }
+
+        for cmd in &self.cmds {
+            match &cmd.kind {
+                CmdKind::Node { accesses, .. } => {
+                    for access in accesses {
+                        let (from, to) = match access.access.intersects(WRITE_ACCESS) {
+                            true => (
+                                format!("cmd{}", cmd.cmd_index),
+                                format!("res{}", access.resource_index),
+                            ),
+                            false => (
+                                format!("res{}", access.resource_index),
+                                format!("cmd{}", cmd.cmd_index),
+                            ),
+                        };
+                        let _ = writeln!(
+                            dot,
+                            "    {} -> {} [label=\"{:?}\"];",
+                            from, to, access.access
+                        );
+                    }
+                }
+                CmdKind::Transition(transition) => {
+                    let _ = writeln!(
+                        dot,
+                        "    res{} -> cmd{} [label=\"{:?} -> {:?}\", style=dashed];",
+                        transition.resource_index,
+                        cmd.cmd_index,
+                        transition.prev_access,
+                        transition.next_access
+                    );
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Formats the schedule of the last update as a Graphviz DOT graph, see [Diagnostics::to_dot].
+    pub fn to_dot(&self) -> Result<String, Error> {
+        Ok(self.diagnostics()?.to_dot())
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::Transition,
+        diagnostics::test_util::{node, resource},
+        runtime::RuntimeHeap,
+    };
+
+    #[test]
+    fn schedule_to_dot() {
+        let diagnostics = Diagnostics {
+            resources: vec![resource("\"color\"", RuntimeHeap::default(), 0..64, 0..3)],
+            cmds: vec![
+                node(0, Some(0), "draw", &[(0, AccessFlags::RENDER_TARGET)]),
+                CmdInfo {
+                    cmd_index: 1,
+                    queue_index: Some(0),
+                    kind: CmdKind::Transition(Transition {
+                        resource_index: 0,
+                        prev_access: AccessFlags::RENDER_TARGET,
+                        next_access: AccessFlags::SHADER_RESOURCE,
+                        range: Default::default(),
+                    }),
+                },
+                node(2, Some(0), "blur", &[(0, AccessFlags::SHADER_RESOURCE)]),
+                node(3, Some(1), "upload", &[]),
+            ],
+            heaps: Vec::new(),
+        };
+
+        let dot = diagnostics.to_dot();
+        assert!(dot.starts_with("digraph RenderGraph {"));
+        assert!(dot.contains("subgraph cluster_queue0 {"));
+        assert!(dot.contains("subgraph cluster_queue1 {"));
+        assert!(dot.contains("cmd0 [label=\"0: draw\", shape=box];"));
+        assert!(dot.contains("cmd0 -> cmd1;"));
+        assert!(dot.contains("cmd1 -> cmd2;"));
+        assert!(!dot.contains("cmd2 -> cmd3;"));
+        assert!(dot.contains("res0 [label=\"\\\"color\\\"\", shape=note];"));
+        assert!(dot
+            .contains("res0 -> cmd1 [label=\"RENDER_TARGET -> SHADER_RESOURCE\", style=dashed];"));
+
+        // Writes point from the node to the resource, reads from the resource to the node.
+        assert!(dot.contains("cmd0 -> res0 [label=\"RENDER_TARGET\"];"));
+        assert!(dot.contains("res0 -> cmd2 [label=\"SHADER_RESOURCE\"];"));
+    }
+}
@@ -0,0 +1,330 @@
+//! Occupancy and aliasing of the heaps of scheduled render graphs.
+
+use std::{fmt::Write, ops::Range};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::Diagnostics;
+
+/// Width of the command timeline of the text rendering, in characters.
+const TEXT_WIDTH: usize = 64;
+/// Width of the SVG rendering, in pixels.
+const SVG_WIDTH: f64 = 960.0;
+/// Height of the heap in the SVG rendering, in pixels.
+const SVG_HEIGHT: f64 = 320.0;
+
+/// Byte range of a heap occupied by a resource over a range of commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeapAllocation {
+    /// Index of the resource.
+    pub resource_index: usize,
+    /// Name of the resource.
+    pub name: String,
+    /// Byte range of the resource in the heap.
+    pub bytes: Range<u64>,
+    /// Range of the command indices the resource is alive for.
+    pub cmds: Range<u32>,
+}
+
+impl HeapAllocation {
+    /// Returns whether two allocations share memory while both are alive.
+    pub fn overlaps(&self, other: &HeapAllocation) -> bool {
+        self.bytes.start < other.bytes.end
+            && other.bytes.start < self.bytes.end
+            && self.cmds.start < other.cmds.end
+            && other.cmds.start < self.cmds.end
+    }
+
+    /// Returns whether two allocations share memory at different times, i.e. alias each other.
+    pub fn aliases(&self, other: &HeapAllocation) -> bool {
+        self.bytes.start < other.bytes.end
+            && other.bytes.start < self.bytes.end
+            && !(self.cmds.start < other.cmds.end && other.cmds.start < self.cmds.end)
+    }
+}
+
+/// Occupancy of a heap over the commands of a render graph.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeapOccupancy {
+    /// Index of the heap.
+    pub heap_index: usize,
+    /// Size of the heap in bytes.
+    pub size: u64,
+    /// Number of commands of the render graph, the length of the timeline.
+    pub num_cmds: u32,
+    /// Allocations placed in the heap, ordered by offset.
+    pub allocations: Vec<HeapAllocation>,
+    /// Maximum number of bytes occupied by live resources at any command.
+    pub peak_usage: u64,
+    /// Index of the first command at which the usage peaks, `None` if the heap is never used.
+    pub peak_cmd_index: Option<u32>,
+    /// End of the highest byte range occupied by any resource.
+    pub high_water_mark: u64,
+}
+
+impl HeapOccupancy {
+    /// Returns the fraction of the bytes below the high water mark which are never needed at the same time.
+    ///
+    /// This is 0 if the peak usage is packed without gaps, and approaches 1 the more the live resources are spread
+    /// across the heap.
+    pub fn fragmentation(&self) -> f64 {
+        match self.high_water_mark {
+            0 => 0.0,
+            high_water_mark => 1.0 - self.peak_usage as f64 / high_water_mark as f64,
+        }
+    }
+
+    /// Returns the pairs of indices of the allocations aliasing each other.
+    pub fn aliases(&self) -> Vec<(usize, usize)> {
+        let mut aliases = Vec::new();
+        for (index, allocation) in self.allocations.iter().enumerate() {
+            for (other_index, other) in self.allocations.iter().enumerate().skip(index + 1) {
+                if allocation.aliases(other) {
+                    aliases.push((index, other_index));
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Renders the occupancy as text, one line per allocation with its lifetime on the command timeline.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "heap {}: {} bytes, high water mark {} bytes, peak usage {} bytes{}, fragmentation {:.1}%",
+            self.heap_index,
+            self.size,
+            self.high_water_mark,
+            self.peak_usage,
+            match self.peak_cmd_index {
+                Some(cmd_index) => format!(" at command {}", cmd_index),
+                None => String::new(),
+            },
+            self.fragmentation() * 100.0
+        );
+
+        let columns = (self.num_cmds as usize).clamp(1, TEXT_WIDTH);
+        let column = |cmd_index: u32| cmd_index as usize * columns / self.num_cmds.max(1) as usize;
+
+        for allocation in &self.allocations {
+            let mut timeline = vec![b'.'; columns];
+            let start = column(allocation.cmds.start).min(columns - 1);
+            let end = column(allocation.cmds.end).clamp(start + 1, columns);
+            timeline[start..end].fill(b'#');
+
+            let _ = writeln!(
+                text,
+                "  {:>10}..{:<10} |{}| {}",
+                allocation.bytes.start,
+                allocation.bytes.end,
+                String::from_utf8_lossy(&timeline),
+                allocation.name
+            );
+        }
+
+        text
+    }
+
+    /// Renders the occupancy as an SVG image, with commands along the x axis and heap offsets along the y axis.
+    pub fn to_svg(&self) -> String {
+        let x = |cmd_index: u32| cmd_index as f64 * SVG_WIDTH / self.num_cmds.max(1) as f64;
+        let y = |offset: u64| {
+            offset as f64 * SVG_HEIGHT / self.size.max(self.high_water_mark).max(1) as f64
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"10\">",
+            SVG_WIDTH,
+            SVG_HEIGHT + 20.0
+        );
+        let _ = writeln!(
+            svg,
+            "  <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"#eee\" stroke=\"#888\"/>",
+            SVG_WIDTH, SVG_HEIGHT
+        );
+
+        for (index, allocation) in self.allocations.iter().enumerate() {
+            let (left, top) = (x(allocation.cmds.start), y(allocation.bytes.start));
+            let (width, height) = (
+                x(allocation.cmds.end) - left,
+                (y(allocation.bytes.end) - top).max(1.0),
+            );
+
+            let _ = writeln!(
+                svg,
+                "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"hsl({}, 60%, 70%)\" stroke=\"#333\"><title>{} [{}..{}) cmds {}..{}</title></rect>",
+                left,
+                top,
+                width,
+                height,
+                index * 47 % 360,
+                escape(&allocation.name),
+                allocation.bytes.start,
+                allocation.bytes.end,
+                allocation.cmds.start,
+                allocation.cmds.end
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            "  <text x=\"0\" y=\"{}\">heap {}: peak {} / {} bytes</text>",
+            SVG_HEIGHT + 14.0,
+            self.heap_index,
+            self.peak_usage,
+            self.size
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Diagnostics {
+    /// Returns the occupancy of every heap, from the placements and lifetimes of the resources.
+    ///
+    /// Resources with a dedicated allocation or without a lifetime aren't part of any heap.
+    pub fn heap_occupancy(&self) -> Vec<HeapOccupancy> {
+        let num_cmds = self
+            .resources
+            .iter()
+            .filter_map(|resource| resource.lifetime.as_ref())
+            .map(|lifetime| lifetime.end)
+            .fold(self.cmds.len() as u32, u32::max);
+
+        self.heaps
+            .iter()
+            .enumerate()
+            .map(|(heap_index, heap)| {
+                let mut allocations = self
+                    .resources
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, resource)| {
+                        !heap.runtime_heap.ptr.is_null()
+                            && resource.placement.heap.ptr == heap.runtime_heap.ptr
+                    })
+                    .filter_map(|(resource_index, resource)| {
+                        let offset = resource.placement.offset;
+                        Some(HeapAllocation {
+                            resource_index,
+                            name: resource.name.clone(),
+                            bytes: offset..offset + resource.memory_requirement.size,
+                            cmds: resource.lifetime.clone()?,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                allocations
+                    .sort_by_key(|allocation| (allocation.bytes.start, allocation.cmds.start));
+
+                // The usage only grows when a resource becomes alive, so the peak is at the start of a lifetime.
+                let mut starts = allocations
+                    .iter()
+                    .map(|allocation| allocation.cmds.start)
+                    .collect::<Vec<_>>();
+                starts.sort_unstable();
+                starts.dedup();
+
+                let (mut peak_usage, mut peak_cmd_index) = (0, None);
+                for cmd_index in starts {
+                    let usage = allocations
+                        .iter()
+                        .filter(|allocation| allocation.cmds.contains(&cmd_index))
+                        .map(|allocation| allocation.bytes.end - allocation.bytes.start)
+                        .sum::<u64>();
+                    if usage > peak_usage {
+                        peak_usage = usage;
+                        peak_cmd_index = Some(cmd_index);
+                    }
+                }
+
+                HeapOccupancy {
+                    heap_index,
+                    size: heap.size,
+                    num_cmds,
+                    high_water_mark: allocations
+                        .iter()
+                        .map(|allocation| allocation.bytes.end)
+                        .max()
+                        .unwrap_or(0),
+                    allocations,
+                    peak_usage,
+                    peak_cmd_index,
+                }
+            })
+            .collect()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_void;
+
+    use super::*;
+    use crate::{
+        diagnostics::test_util::{heap, resource},
+        runtime::RuntimeHeap,
+    };
+
+    #[test]
+    fn aliasing_report() {
+        let runtime_heap = RuntimeHeap {
+            ptr: 1 as *mut c_void,
+        };
+
+        let diagnostics = Diagnostics {
+            resources: vec![
+                resource("shadow", runtime_heap, 0..512, 0..2),
+                resource("bloom", runtime_heap, 0..256, 2..4),
+                resource("<lut>", runtime_heap, 768..1024, 1..4),
+                resource("back", RuntimeHeap::default(), 0..4096, 0..4),
+            ],
+            cmds: Vec::new(),
+            heaps: vec![heap(runtime_heap, 1024)],
+        };
+
+        let occupancy = diagnostics.heap_occupancy();
+        assert_eq!(occupancy.len(), 1);
+
+        let heap = &occupancy[0];
+        assert_eq!(heap.num_cmds, 4);
+
+        let names = heap
+            .allocations
+            .iter()
+            .map(|allocation| allocation.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["shadow", "bloom", "<lut>"]);
+        assert_eq!(heap.aliases(), [(0, 1)]);
+        assert!(!heap.allocations[0].overlaps(&heap.allocations[1]));
+
+        // Shadow and LUT are alive at command 1, with the 256 bytes in between never used at the same time.
+        assert_eq!(heap.peak_usage, 768);
+        assert_eq!(heap.peak_cmd_index, Some(1));
+        assert_eq!(heap.high_water_mark, 1024);
+        assert_eq!(heap.fragmentation(), 0.25);
+
+        let text = heap.to_text();
+        assert!(text.starts_with(
+            "heap 0: 1024 bytes, high water mark 1024 bytes, peak usage 768 bytes at command 1"
+        ));
+        assert!(text.contains("|##..| shadow"));
+        assert!(text.contains("|..##| bloom"));
+
+        let svg = heap.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("<title>&lt;lut&gt; [768..1024) cmds 1..4</title>"));
+        assert_eq!(svg.matches("<rect ").count(), 4);
+    }
+}
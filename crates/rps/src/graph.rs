@@ -0,0 +1,852 @@
+//! Render graph wrappers.
+
+use std::{marker::PhantomData, mem, ops::Range, ptr, slice};
+
+use bitflags::bitflags;
+
+use crate::{
+    builder::{self, BuildCallback, DataArg, GraphBuilder},
+    call, guard,
+    phase::PhaseStorage,
+    program::{Program, RpslEntry},
+    runtime::{ResourceDesc, RuntimeResource},
+    signature::{EntrySignature, RawSignature},
+    Device, Error,
+};
+use rps_sys as ffi;
+
+/// Value of the GPU completed frame index to indicate that no frames are known to have completed yet.
+pub const GPU_COMPLETED_FRAME_INDEX_NONE: u64 = u64::MAX;
+
+bitflags! {
+    /// Bitflags for the capabilities of a queue.
+    pub struct QueueFlags: u32 {
+        /// Graphics capabilities.
+        const GRAPHICS = ffi::RpsQueueFlagBits_RPS_QUEUE_FLAG_GRAPHICS;
+        /// Compute capabilities.
+        const COMPUTE = ffi::RpsQueueFlagBits_RPS_QUEUE_FLAG_COMPUTE;
+        /// Copy capabilities.
+        const COPY = ffi::RpsQueueFlagBits_RPS_QUEUE_FLAG_COPY;
+    }
+}
+
+bitflags! {
+    /// Bitflags for scheduling behavior.
+    ///
+    /// If empty when passed to [RenderGraph::update], the flags specified at render graph creation are used instead.
+    #[derive(Default)]
+    pub struct ScheduleFlags: u32 {
+        /// Command nodes are kept in the program order.
+        const KEEP_PROGRAM_ORDER = ffi::RpsScheduleFlagBits_RPS_SCHEDULE_KEEP_PROGRAM_ORDER_BIT;
+
+        /// Schedules in favor of reducing total GPU memory usage. Possible strategies include minimizing transient
+        /// resource lifetimes and aggressive aliasing. This may increase the number of barriers generated.
+        const PREFER_MEMORY_SAVING = ffi::RpsScheduleFlagBits_RPS_SCHEDULE_PREFER_MEMORY_SAVING_BIT;
+
+        /// Schedules commands randomly (without changing program logic). Mostly useful for testing purposes. If
+        /// KEEP_PROGRAM_ORDER is set, this flag will have no effect.
+        const RANDOM_ORDER = ffi::RpsScheduleFlagBits_RPS_SCHEDULE_RANDOM_ORDER_BIT;
+
+        /// Avoids alternating between graphics and compute work on the same queue. This can help for some
+        /// architectures where switching between graphics and compute produces extra overhead.
+        const MINIMIZE_COMPUTE_GFX_SWITCH = ffi::RpsScheduleFlagBits_RPS_SCHEDULE_MINIMIZE_COMPUTE_GFX_SWITCH_BIT;
+
+        /// Disables dead code elimination optimization. By default, RPS removes nodes that have no visible effect (not
+        /// contributing to modification of external, temporal, persistent or CPU shared resources).
+        const DISABLE_DEAD_CODE_ELIMINATION = ffi::RpsScheduleFlagBits_RPS_SCHEDULE_DISABLE_DEAD_CODE_ELIMINATION_BIT;
+
+        /// Disables work pipelining based on the workload type.
+        const WORKLOAD_TYPE_PIPELINING_DISABLE =
+            ffi::RpsScheduleFlagBits_RPS_SCHEDULE_WORKLOAD_TYPE_PIPELINING_DISABLE_BIT;
+
+        /// Performs aggressive work pipelining based on the workload type. If WORKLOAD_TYPE_PIPELINING_DISABLE is set,
+        /// this flag will have no effect.
+        const WORKLOAD_TYPE_PIPELINING_AGGRESSIVE =
+            ffi::RpsScheduleFlagBits_RPS_SCHEDULE_WORKLOAD_TYPE_PIPELINING_AGGRESSIVE_BIT;
+    }
+}
+
+bitflags! {
+    /// Bitflags for diagnostic output of a render graph update.
+    #[derive(Default)]
+    pub struct DiagnosticFlags: u32 {
+        /// Dumps the render graph before scheduling.
+        const PRE_SCHEDULE_DUMP = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_PRE_SCHEDULE_DUMP;
+
+        /// Dumps the render graph after scheduling.
+        const POST_SCHEDULE_DUMP = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_POST_SCHEDULE_DUMP;
+
+        /// Dumps the directed acyclic graph of the nodes.
+        const DAG_DUMP = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_DAG_DUMP;
+
+        /// Includes RPSL source locations in the dumps.
+        const SOURCE_LOCATION = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_SOURCE_LOCATION;
+
+        /// Sets debug names of the runtime resources.
+        const RUNTIME_DEBUG_NAMES = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_RUNTIME_DEBUG_NAMES;
+
+        /// Enables all diagnostic output.
+        const ALL = ffi::RpsDiagnosticFlagBits_RPS_DIAGNOSTIC_ENABLE_ALL;
+    }
+}
+
+/// Argument of the main entry passed to [RenderGraph::update].
+enum UpdateArg {
+    /// Plain data argument of the given size.
+    Data(usize),
+    /// Resource argument with its description.
+    Resource(Box<ffi::RpsResourceDesc>),
+}
+
+/// Helper structure to build `ffi::RpsRenderGraphUpdateInfo` for a render graph update.
+///
+/// Arguments are borrowed for the lifetime of the update info, so they remain valid while RPS reads them.
+pub struct UpdateInfo<'b> {
+    frame_index: u64,
+    gpu_completed_frame_index: u64,
+    schedule_flags: ScheduleFlags,
+    diagnostic_flags: DiagnosticFlags,
+    args: Vec<UpdateArg>,
+    arg_ptrs: Vec<ffi::RpsConstant>,
+    arg_resources: Vec<*const RuntimeResource>,
+    _marker: PhantomData<&'b ()>,
+}
+
+impl<'b> UpdateInfo<'b> {
+    /// Create a new update info for the given frame.
+    pub fn new(frame_index: u64) -> Self {
+        Self {
+            frame_index,
+            gpu_completed_frame_index: GPU_COMPLETED_FRAME_INDEX_NONE,
+            schedule_flags: ScheduleFlags::empty(),
+            diagnostic_flags: DiagnosticFlags::empty(),
+            args: Vec::new(),
+            arg_ptrs: Vec::new(),
+            arg_resources: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Specify the index of the last frame known to have completed on the GPU.
+    ///
+    /// Defaults to [GPU_COMPLETED_FRAME_INDEX_NONE].
+    pub fn gpu_completed_frame_index(&mut self, index: u64) -> &mut Self {
+        self.gpu_completed_frame_index = index;
+        self
+    }
+
+    /// Specify the scheduling behavior of this update.
+    pub fn schedule_flags(&mut self, flags: ScheduleFlags) -> &mut Self {
+        self.schedule_flags = flags;
+        self
+    }
+
+    /// Specify the diagnostic output of this update.
+    pub fn diagnostic_flags(&mut self, flags: DiagnosticFlags) -> &mut Self {
+        self.diagnostic_flags = flags;
+        self
+    }
+
+    /// Append a plain data argument of the main entry.
+    pub fn arg<T: DataArg>(&mut self, value: &'b T) -> &mut Self {
+        self.args.push(UpdateArg::Data(mem::size_of::<T>()));
+        self.arg_ptrs.push(value as *const T as ffi::RpsConstant);
+        self.arg_resources.push(ptr::null());
+        self
+    }
+
+    /// Append a resource argument of the main entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `desc` - Description of the external resource.
+    /// * `resources` - Runtime resources, one per temporal layer of the resource. Empty if the runtime resources aren't
+    ///                 known yet.
+    pub fn resource_arg(
+        &mut self,
+        desc: &ResourceDesc,
+        resources: &'b [RuntimeResource],
+    ) -> &mut Self {
+        let desc = Box::new(ffi::RpsResourceDesc::from(desc));
+        self.arg_ptrs
+            .push(desc.as_ref() as *const _ as ffi::RpsConstant);
+        self.args.push(UpdateArg::Resource(desc));
+        self.arg_resources.push(if resources.is_empty() {
+            ptr::null()
+        } else {
+            resources.as_ptr()
+        });
+        self
+    }
+
+    /// Checks the arguments against the parameters of the main entry.
+    ///
+    /// Fails with [Error::InvalidArguments] on a count mismatch and [Error::TypeMismatch] if an argument doesn't match
+    /// the size or kind of its parameter.
+    pub fn validate(&self, signature: &EntrySignature) -> Result<(), Error> {
+        if signature.params.len() != self.args.len() {
+            return Err(Error::InvalidArguments);
+        }
+
+        for (param, arg) in signature.params.iter().zip(&self.args) {
+            let matches = match arg {
+                UpdateArg::Data(size) => !param.is_resource() && param.data_size() == *size,
+                UpdateArg::Resource(_) => param.is_resource(),
+            };
+
+            if !matches {
+                return Err(Error::TypeMismatch);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_ffi(&self) -> ffi::RpsRenderGraphUpdateInfo {
+        let has_resources = self.arg_resources.iter().any(|ptr| !ptr.is_null());
+
+        ffi::RpsRenderGraphUpdateInfo {
+            frameIndex: self.frame_index,
+            gpuCompletedFrameIndex: self.gpu_completed_frame_index,
+            scheduleFlags: self.schedule_flags.bits(),
+            diagnosticFlags: self.diagnostic_flags.bits(),
+            numArgs: self.arg_ptrs.len() as u32,
+            ppArgs: if self.arg_ptrs.is_empty() {
+                ptr::null()
+            } else {
+                self.arg_ptrs.as_ptr()
+            },
+            ppArgResources: if has_resources {
+                self.arg_resources.as_ptr()
+            } else {
+                ptr::null()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Helper structure to build `ffi::RpsRenderGraphCreateInfo` for a new render graph.
+#[derive(Default)]
+pub struct RenderGraphCreateInfo {
+    queues: Vec<ffi::RpsQueueFlags>,
+    schedule_flags: ScheduleFlags,
+    entry: Option<RpslEntry>,
+    signature: Option<EntrySignature>,
+}
+
+impl RenderGraphCreateInfo {
+    /// Create a new render graph create info.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a queue the render graph can schedule work to.
+    ///
+    /// The first queue must have graphics capabilities. If no queues are specified, a single graphics queue is used.
+    pub fn queue(&mut self, flags: QueueFlags) -> &mut Self {
+        self.queues.push(flags.bits());
+        self
+    }
+
+    /// Specify the default scheduling behavior of the render graph.
+    pub fn schedule_flags(&mut self, flags: ScheduleFlags) -> &mut Self {
+        self.schedule_flags = flags;
+        self
+    }
+
+    /// Specify the RPSL entry point to build the render graph from.
+    pub fn entry(&mut self, entry: RpslEntry) -> &mut Self {
+        self.entry = Some(entry);
+        self
+    }
+
+    /// Specify the signature of the main entry.
+    ///
+    /// Required to build the render graph with [RenderGraph::set_build_callback].
+    pub fn signature(&mut self, signature: EntrySignature) -> &mut Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    fn to_ffi(&self) -> ffi::RpsRenderGraphCreateInfo {
+        let mut create_info = ffi::RpsRenderGraphCreateInfo::default();
+        create_info.scheduleInfo.scheduleFlags = self.schedule_flags.bits();
+
+        if let Some(entry) = self.entry {
+            create_info.mainEntryCreateInfo.hRpslEntryPoint = entry.raw();
+        }
+
+        if !self.queues.is_empty() {
+            create_info.scheduleInfo.numQueues = self.queues.len() as u32;
+            create_info.scheduleInfo.pQueueInfos = self.queues.as_ptr();
+        }
+
+        create_info
+    }
+}
+
+/// Value used by RPS for absent indices.
+const INDEX_NONE: u32 = u32::MAX;
+
+/// Sequence of commands to be submitted to a single queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandBatch {
+    /// Index of the queue to submit the batch to.
+    pub queue_index: u32,
+    /// Range of the command indices of the batch.
+    pub cmds: Range<u32>,
+    /// Indices of the fences to wait for before executing the batch.
+    pub wait_fences: Vec<u32>,
+    /// Index of the fence to signal after executing the batch, if any.
+    pub signal_fence: Option<u32>,
+}
+
+/// Layout of the command batches of a render graph, one batch per queue submission.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchLayout {
+    /// Command batches in submission order.
+    pub batches: Vec<CommandBatch>,
+    /// Number of fence signals, fence indices are in `0..num_fence_signals`.
+    pub num_fence_signals: u32,
+}
+
+impl BatchLayout {
+    /// Returns an iterator over the command batches.
+    pub fn iter(&self) -> slice::Iter<'_, CommandBatch> {
+        self.batches.iter()
+    }
+}
+
+impl<'b> IntoIterator for &'b BatchLayout {
+    type Item = &'b CommandBatch;
+    type IntoIter = slice::Iter<'b, CommandBatch>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.batches.iter()
+    }
+}
+
+impl From<&ffi::RpsRenderGraphBatchLayout> for BatchLayout {
+    fn from(value: &ffi::RpsRenderGraphBatchLayout) -> Self {
+        let batches = if value.numCmdBatches == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(value.pCmdBatches, value.numCmdBatches as usize) }
+        };
+
+        let batches = batches
+            .iter()
+            .map(|batch| {
+                let wait_fences = if batch.numWaitFences == 0 {
+                    Vec::new()
+                } else {
+                    unsafe {
+                        slice::from_raw_parts(
+                            value.pWaitFenceIndices.add(batch.waitFencesBegin as usize),
+                            batch.numWaitFences as usize,
+                        )
+                    }
+                    .to_vec()
+                };
+
+                CommandBatch {
+                    queue_index: batch.queueIndex,
+                    cmds: batch.cmdBegin..batch.cmdBegin + batch.numCmds,
+                    wait_fences,
+                    signal_fence: match batch.signalFenceIndex {
+                        INDEX_NONE => None,
+                        index => Some(index),
+                    },
+                }
+            })
+            .collect();
+
+        BatchLayout {
+            batches,
+            num_fence_signals: value.numFenceSignals,
+        }
+    }
+}
+
+/// RPS render graph object.
+///
+/// A render graph is the central object to schedule, allocate and record the commands of a frame. It can't outlive
+/// the device it was created from.
+pub struct RenderGraph<'a> {
+    /// Raw handle.
+    handle: ffi::RpsRenderGraph,
+    /// Signature of the main entry, if known, either specified or reflected from the RPSL entry.
+    signature: Option<EntrySignature>,
+    /// Storage of the raw signature (keep alive until the render graph is destroyed).
+    raw_signature: Option<RawSignature>,
+    /// Main entry program with the bound node closures (destroyed along with the render graph).
+    main_entry: Program<'a>,
+    /// Storage of the custom phases (keep alive until the render graph is destroyed).
+    phases: PhaseStorage,
+    /// Closure to build the render graph on update.
+    build_callback: Option<Box<BuildCallback<'a>>>,
+    /// Index of the frame of the last update.
+    frame_index: u64,
+    _device: PhantomData<&'a Device>,
+}
+
+impl Device {
+    /// Creates a render graph object.
+    pub fn create_render_graph(
+        &self,
+        create_info: &RenderGraphCreateInfo,
+    ) -> Result<RenderGraph<'_>, Error> {
+        let signature = match (&create_info.signature, create_info.entry) {
+            (Some(signature), _) => Some(signature.clone()),
+            (None, Some(entry)) => Some(entry.signature()?),
+            (None, None) => None,
+        };
+
+        let raw_signature = create_info
+            .signature
+            .as_ref()
+            .map(EntrySignature::to_raw)
+            .transpose()?;
+
+        let mut ffi_create_info = create_info.to_ffi();
+        if let Some(raw_signature) = &raw_signature {
+            ffi_create_info.mainEntryCreateInfo.pSignatureDesc = raw_signature.desc();
+        }
+
+        let mut handle = ptr::null_mut();
+        let mut phases = PhaseStorage::default();
+
+        phases.collect(|| {
+            call!(ffi::rpsRenderGraphCreate(
+                self.handle,
+                &ffi_create_info,
+                &mut handle
+            ))
+        })?;
+
+        let main_entry = Program::main_entry(unsafe { ffi::rpsRenderGraphGetMainEntry(handle) });
+
+        Ok(RenderGraph {
+            handle,
+            signature,
+            main_entry,
+            raw_signature,
+            phases,
+            build_callback: None,
+            frame_index: 0,
+            _device: PhantomData,
+        })
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Returns the raw render graph handle.
+    pub fn handle(&self) -> ffi::RpsRenderGraph {
+        self.handle
+    }
+
+    /// Returns the signature of the main entry, if known.
+    ///
+    /// For render graphs created from an RPSL entry, the signature is reflected from the compiled entry.
+    pub fn signature(&self) -> Option<&EntrySignature> {
+        self.signature.as_ref()
+    }
+
+    /// Returns the index of the frame of the last update.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Returns the main entry program to bind node implementations to.
+    pub fn main_entry(&mut self) -> &mut Program<'a> {
+        &mut self.main_entry
+    }
+
+    /// Sets a closure to build the render graph from Rust instead of the RPSL main entry.
+    ///
+    /// The closure is invoked on every [RenderGraph::update] and can add the nodes declared in the [EntrySignature] passed
+    /// at creation time.
+    pub fn set_build_callback(
+        &mut self,
+        callback: impl FnMut(&mut GraphBuilder) -> Result<(), Error> + 'a,
+    ) {
+        self.build_callback = Some(Box::new(callback));
+    }
+
+    /// Updates the render graph for a new frame.
+    ///
+    /// This builds the graph from the main entry, schedules the commands and allocates the resources.
+    ///
+    /// If the signature of the main entry is known, the arguments are checked against its parameters, failing with
+    /// [Error::InvalidArguments] on a count mismatch and [Error::TypeMismatch] on a type mismatch.
+    pub fn update(&mut self, update_info: &UpdateInfo) -> Result<(), Error> {
+        if let Some(signature) = &self.signature {
+            update_info.validate(signature)?;
+        }
+
+        self.frame_index = update_info.frame_index;
+        let mut update_info = update_info.to_ffi();
+
+        let handle = self.handle;
+        match self.build_callback.as_deref_mut() {
+            Some(callback) => {
+                // Without a signature, the build closure can declare resources but not add nodes.
+                let empty;
+                let signature = match &self.signature {
+                    Some(signature) => signature,
+                    None => {
+                        empty = EntrySignature::default();
+                        &empty
+                    }
+                };
+
+                update_info.pfnBuildCallback = Some(builder::build_render_graph);
+                builder::with_build_callback(callback, signature, || {
+                    call!(ffi::rpsRenderGraphUpdate(handle, &update_info))
+                })
+            }
+            None => call!(ffi::rpsRenderGraphUpdate(handle, &update_info)),
+        }
+    }
+
+    /// Records a range of the scheduled commands to a command buffer.
+    ///
+    /// An [Error::Callback] returned by a node callback is returned as is, with the original error as its source.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd_buffer` - Runtime command buffer to record the commands to.
+    /// * `frame_index` - Index of the frame being recorded.
+    /// * `cmds` - Range of the command indices to record.
+    pub fn record_commands(
+        &self,
+        cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        frame_index: u64,
+        cmds: Range<u32>,
+    ) -> Result<(), Error> {
+        let record_info = ffi::RpsRenderGraphRecordCommandInfo {
+            hCmdBuffer: cmd_buffer,
+            frameIndex: frame_index,
+            cmdBeginIndex: cmds.start,
+            numCmds: cmds.end.saturating_sub(cmds.start),
+            ..Default::default()
+        };
+
+        call!(ffi::rpsRenderGraphRecordCommands(self.handle, &record_info))
+    }
+
+    /// Returns the command batch layout of the last update.
+    ///
+    /// Each batch is meant to be recorded to its own command buffer and submitted to its queue after waiting for the
+    /// `wait_fences`, signaling `signal_fence` on completion.
+    pub fn batch_layout(&self) -> Result<BatchLayout, Error> {
+        self.graph_ref().batch_layout()
+    }
+
+    /// Records the commands of a batch of the last update to a command buffer.
+    pub fn record_batch(
+        &self,
+        batch: &CommandBatch,
+        cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+    ) -> Result<(), Error> {
+        self.record_commands(cmd_buffer, self.frame_index, batch.cmds.clone())
+    }
+}
+
+impl<'a> Drop for RenderGraph<'a> {
+    fn drop(&mut self) {
+        guard::destroy(|| unsafe { ffi::rpsRenderGraphDestroy(self.handle) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        runtime::{self, AccessFlags, Format, ResourceDesc},
+        signature::{NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    struct Dummy;
+    impl runtime::Callbacks for Dummy {}
+
+    #[test]
+    fn create_render_graph() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().queue(QueueFlags::GRAPHICS))
+            .unwrap();
+        assert!(graph.signature().is_none());
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+        drop(graph);
+    }
+
+    #[test]
+    fn build_render_graph() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "clear",
+            vec![ParamDesc::image(
+                "target",
+                AccessFlags::RENDER_TARGET | AccessFlags::CLEAR,
+            )],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        let mut builds = 0;
+        graph.set_build_callback(|builder| {
+            let desc = ResourceDesc::image_2d(Format::R8G8B8A8Unorm, 1280, 720);
+            let target = builder.declare_resource("target", 0, &desc)?;
+
+            builder.add_node("clear", &[&runtime::image_view(target)])?;
+
+            // Unknown node declarations and mismatching arguments are rejected.
+            assert!(matches!(
+                builder.add_node("draw", &[]),
+                Err(Error::UnknownNode)
+            ));
+            assert!(matches!(
+                builder.add_node("clear", &[&0_u32]),
+                Err(Error::TypeMismatch)
+            ));
+
+            // Plain data of the size of a view isn't a resource argument.
+            assert!(matches!(
+                builder.add_node("clear", &[&[0_u8; mem::size_of::<runtime::ImageView>()]]),
+                Err(Error::TypeMismatch)
+            ));
+
+            builds += 1;
+            Ok(())
+        });
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+        graph
+            .update(
+                UpdateInfo::new(1)
+                    .gpu_completed_frame_index(0)
+                    .schedule_flags(ScheduleFlags::KEEP_PROGRAM_ORDER)
+                    .diagnostic_flags(DiagnosticFlags::RUNTIME_DEBUG_NAMES),
+            )
+            .unwrap();
+
+        drop(graph);
+        assert_eq!(builds, 2);
+    }
+
+    #[test]
+    fn update_args() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.params.push(ParamDesc::new::<u32>("count"));
+        signature
+            .params
+            .push(ParamDesc::image("back_buffer", AccessFlags::PRESENT));
+        signature.params.push(ParamDesc {
+            array_size: 4,
+            ..ParamDesc::new::<f32>("weights")
+        });
+        signature.max_external_resources = 1;
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        let mut counts = Vec::new();
+        graph.set_build_callback(|builder| {
+            counts.push(*builder.param::<u32>(0)?);
+            builder.param_resource(1)?;
+            assert_eq!(*builder.param::<[f32; 4]>(2)?, [0.5; 4]);
+            assert!(matches!(builder.param::<f32>(2), Err(Error::TypeMismatch)));
+            Ok(())
+        });
+
+        let count = 3_u32;
+        let weights = [0.5_f32; 4];
+        let back_buffer = ResourceDesc::image_2d(Format::B8G8R8A8Unorm, 1280, 720);
+        let back_buffers = [RuntimeResource::default()];
+
+        // Missing and mistyped arguments are rejected before reaching RPS.
+        assert!(matches!(
+            graph.update(UpdateInfo::new(0).arg(&count)),
+            Err(Error::InvalidArguments)
+        ));
+        assert!(matches!(
+            graph.update(
+                UpdateInfo::new(0)
+                    .arg(&0_u64)
+                    .resource_arg(&back_buffer, &back_buffers)
+                    .arg(&weights)
+            ),
+            Err(Error::TypeMismatch)
+        ));
+        assert!(matches!(
+            graph.update(
+                UpdateInfo::new(0)
+                    .arg(&count)
+                    .resource_arg(&back_buffer, &back_buffers)
+                    .arg(&weights[0])
+            ),
+            Err(Error::TypeMismatch)
+        ));
+
+        graph
+            .update(
+                UpdateInfo::new(0)
+                    .arg(&count)
+                    .resource_arg(&back_buffer, &back_buffers)
+                    .arg(&weights),
+            )
+            .unwrap();
+
+        // Without runtime resources no resource array is passed to RPS.
+        let mut info = UpdateInfo::new(1);
+        info.arg(&count)
+            .resource_arg(&back_buffer, &[])
+            .arg(&weights);
+        assert!(info.to_ffi().ppArgResources.is_null());
+
+        drop(graph);
+        assert_eq!(counts, [3]);
+    }
+
+    #[test]
+    fn batch_layout() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "clear",
+            vec![ParamDesc::image(
+                "target",
+                AccessFlags::RENDER_TARGET | AccessFlags::CLEAR,
+            )],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            let desc = ResourceDesc::image_2d(Format::R8G8B8A8Unorm, 64, 64);
+            let target = builder.declare_resource("target", 0, &desc)?;
+            builder.add_node("clear", &[&runtime::image_view(target)])?;
+            Ok(())
+        });
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let layout = graph.batch_layout().unwrap();
+        assert!(!layout.batches.is_empty());
+
+        for batch in &layout {
+            assert_eq!(batch.queue_index, 0);
+            assert!(batch.wait_fences.is_empty());
+
+            graph
+                .record_batch(batch, ffi::RpsRuntimeCommandBuffer::default())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn bind_node() {
+        use std::cell::RefCell;
+
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "draw",
+            vec![
+                ParamDesc::image("target", AccessFlags::RENDER_TARGET),
+                ParamDesc::new::<u32>("count"),
+            ],
+        ));
+
+        let recorded = RefCell::new(Vec::new());
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            let desc = ResourceDesc::image_2d(Format::R8G8B8A8Unorm, 64, 64);
+            let target = builder.declare_resource("target", 0, &desc)?;
+            builder.add_node("draw", &[&runtime::image_view(target), &7_u32])?;
+            Ok(())
+        });
+
+        graph
+            .main_entry()
+            .bind_node("draw", |context| {
+                assert!(matches!(context.arg::<u64>(1), Err(Error::TypeMismatch)));
+
+                let count = *context.arg::<u32>(1)?;
+                recorded
+                    .borrow_mut()
+                    .push((context.node_name()?.to_string(), count));
+                Ok(())
+            })
+            .unwrap();
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        for batch in &graph.batch_layout().unwrap() {
+            graph
+                .record_batch(batch, ffi::RpsRuntimeCommandBuffer::default())
+                .unwrap();
+        }
+
+        drop(graph);
+        assert_eq!(recorded.into_inner(), [("draw".to_string(), 7)]);
+    }
+
+    #[test]
+    fn node_callback_error() {
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new("draw", Vec::new()));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            builder.add_node("draw", &[])?;
+            Ok(())
+        });
+
+        graph
+            .main_entry()
+            .bind_node("draw", |_| Err(Error::callback("device lost")))
+            .unwrap();
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let layout = graph.batch_layout().unwrap();
+        let batch = layout.iter().next().unwrap();
+        let err = graph
+            .record_batch(batch, ffi::RpsRuntimeCommandBuffer::default())
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Callback(_)));
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "device lost"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Guards for the Rust callbacks invoked by RPS.
+//!
+//! Unwinding across the C boundary is undefined behavior and RPS only passes result codes around, so every trampoline
+//! runs the Rust code it invokes through these guards. A panic, or an [Error::Callback] which can't be represented by
+//! a result code, is kept for the calling thread until the API call which invoked the callback returns it. RPS invokes
+//! the callbacks synchronously from the API calls of the same thread, so every API call runs in a [scope] which
+//! collects the errors of its own callbacks, and leaves the error of an enclosing call, e.g. the record call invoking a
+//! node callback which makes API calls itself, untouched.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    mem,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::Error;
+use rps_sys as ffi;
+
+thread_local! {
+    static PENDING: RefCell<Option<Error>> = RefCell::new(None);
+}
+
+/// Keeps `err` to be returned by the enclosing API call, unless an earlier error is already pending.
+fn stash(err: Error) {
+    PENDING.with(|cell| {
+        cell.borrow_mut().get_or_insert(err);
+    });
+}
+
+/// Runs `f`, returning `default` and recording the panic if it panics.
+pub(crate) fn catch<R>(default: R, f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            stash(Error::CallbackPanicked);
+            drop_payload(payload);
+            default
+        }
+    }
+}
+
+/// Drops a panic payload without unwinding if its drop panics as well.
+fn drop_payload(payload: Box<dyn Any + Send>) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| drop(payload))) {
+        // The payload of the panicking drop may panic the same way, so only this one is forgotten.
+        mem::forget(payload);
+    }
+}
+
+/// Runs `f`, mapping its result to an RPS result code.
+///
+/// A panic maps to [Error::CallbackPanicked], an [Error::Callback] is kept to be returned by the enclosing API call.
+pub(crate) fn catch_result(f: impl FnOnce() -> Result<(), Error>) -> ffi::RpsResult {
+    catch(Error::CallbackPanicked.code(), || match f() {
+        Ok(()) => ffi::RpsResult_RPS_OK,
+        Err(err) => {
+            let code = err.code();
+            if let Error::Callback(_) = err {
+                stash(err);
+            }
+            code
+        }
+    })
+}
+
+/// Returns the error kept on this thread, if any.
+pub(crate) fn take() -> Option<Error> {
+    PENDING.with(|cell| cell.borrow_mut().take())
+}
+
+/// Runs an API call, returning its result along with the error kept by the callbacks it invoked.
+///
+/// The error of an enclosing call is set aside for the duration of the call and restored afterwards.
+#[doc(hidden)]
+pub fn scope<R>(f: impl FnOnce() -> R) -> (R, Option<Error>) {
+    let outer = take();
+    let result = f();
+    let inner = take();
+
+    if outer.is_some() {
+        PENDING.with(|cell| *cell.borrow_mut() = outer);
+    }
+
+    (result, inner)
+}
+
+/// Runs a destroy call from a `Drop` implementation, which can't return the errors of the callbacks it invokes.
+///
+/// The errors are logged with the `log` feature enabled and dropped otherwise.
+pub(crate) fn destroy(f: impl FnOnce()) {
+    let ((), err) = scope(f);
+
+    #[cfg(feature = "log")]
+    if let Some(err) = &err {
+        log::error!(target: "rps", "Callback failed while destroying an object: {}", err);
+    }
+
+    drop(err);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn catch_panic() {
+        assert_eq!(catch(0, || 1), 1);
+        assert!(take().is_none());
+
+        assert_eq!(catch(0, || panic!("callback failure")), 0);
+        assert!(matches!(take(), Some(Error::CallbackPanicked)));
+        assert!(take().is_none());
+
+        assert_eq!(
+            catch_result(|| Err(Error::InvalidData)),
+            ffi::RpsResult_RPS_ERROR_INVALID_DATA
+        );
+        assert!(take().is_none());
+
+        assert_eq!(catch_result(|| panic!()), Error::CallbackPanicked.code());
+        assert!(matches!(take(), Some(Error::CallbackPanicked)));
+    }
+
+    #[test]
+    fn drop_panic_payload() {
+        struct Payload(Arc<AtomicUsize>, bool);
+
+        impl Drop for Payload {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                if self.1 {
+                    panic!("payload drop");
+                }
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        catch((), || panic::panic_any(Payload(drops.clone(), false)));
+        assert!(matches!(take(), Some(Error::CallbackPanicked)));
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        // A payload panicking on drop doesn't unwind out of the guard.
+        catch((), || panic::panic_any(Payload(drops.clone(), true)));
+        assert!(matches!(take(), Some(Error::CallbackPanicked)));
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn nested_scope() {
+        // A callback of the outer call failed before the inner call.
+        catch((), || panic!("outer callback"));
+
+        let (code, err) = scope(|| catch_result(|| Ok(())));
+        assert_eq!(code, ffi::RpsResult_RPS_OK);
+        assert!(err.is_none());
+
+        let ((), err) = scope(|| catch((), || panic!("inner callback")));
+        assert!(matches!(err, Some(Error::CallbackPanicked)));
+
+        // The error of the outer call is still kept for it.
+        assert!(matches!(take(), Some(Error::CallbackPanicked)));
+        assert!(take().is_none());
+    }
+
+    #[test]
+    fn keep_callback_error() {
+        let code = catch_result(|| {
+            Err(Error::callback(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                "heap exhausted",
+            )))
+        });
+        assert_ne!(code, ffi::RpsResult_RPS_OK);
+
+        let err = take().unwrap();
+        assert!(matches!(err, Error::Callback(_)));
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "heap exhausted"
+        );
+    }
+}
@@ -1,28 +1,51 @@
 //! High level Rust wrapper for RPS API.
 
-use std::ptr;
-
+use std::{ptr, sync::Arc};
+
+// Allows derive macros to refer to `::rps` from within this crate.
+extern crate self as rps;
+
+pub mod allocator;
+pub mod backend;
+pub mod builder;
+pub mod cmd;
+pub mod diagnostics;
+pub mod graph;
+#[doc(hidden)]
+pub mod guard;
+pub mod phase;
+mod printer;
+pub mod program;
 pub mod runtime;
+pub mod signature;
 
 use thiserror::Error;
 
+use allocator::{Allocator, AllocatorContext};
+use printer::{PrintCallback, Printer};
+
+pub use cmd::NodeArgs;
+pub use rps_derive::NodeArgs;
+
 use rps_sys as ffi;
 
 /// Helper macro to FFI API and map result to `Error` type.
+///
+/// An error kept by a callback guard during the call takes precedence over the result of the call, see [guard].
 #[macro_export]
 macro_rules! call {
     ($f:expr) => {{
-        let code = unsafe { $f };
-        match code {
-            0 => Ok(()),
-            _ => Err(Error::from(code)),
+        match $crate::guard::scope(|| unsafe { $f }) {
+            (_, Some(err)) => Err(err),
+            (0, None) => Ok(()),
+            (code, None) => Err(Error::from(code)),
         }
     }};
 }
 
 /// Error codes used by operations of the RPS library.
 #[repr(i32)]
-#[derive(Debug, Error, Copy, Clone)]
+#[derive(Debug, Error)]
 pub enum Error {
     #[error("Unspecified error")]
     Unspecified = ffi::RpsResult_RPS_ERROR_UNSPECIFIED,
@@ -128,16 +151,38 @@ pub enum Error {
     #[error("RPS library internal error")]
     InternalError = ffi::RpsResult_RPS_ERROR_INTERNAL_ERROR,
 
+    /// Failure due to a panic in a Rust callback invoked by RPS.
+    #[error("Callback panicked")]
+    CallbackPanicked = i32::MIN,
+
+    /// Failure reported by a Rust callback invoked by RPS, with the error of the callback as the source.
+    #[error("Callback failed")]
+    Callback(#[source] Box<dyn std::error::Error + Send + Sync>) = i32::MIN + 1,
+
     #[error("Unmapped error code: {0}")]
     Unknown(ffi::RpsResult) = i32::MAX,
 }
 
+impl Error {
+    /// Creates an error to return from a callback, preserving `err` as the source of the error returned by the API
+    /// call which invoked the callback.
+    pub fn callback(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Error::Callback(err.into())
+    }
+
+    /// Returns the RPS result code of the error.
+    pub fn code(&self) -> ffi::RpsResult {
+        match self {
+            Error::Unknown(code) => *code,
+            // SAFETY: `Error` is `repr(i32)`, so the discriminant is stored as the leading `i32`.
+            _ => unsafe { *(self as *const Error as *const ffi::RpsResult) },
+        }
+    }
+}
+
 impl From<Error> for ffi::RpsResult {
     fn from(value: Error) -> Self {
-        match value {
-            Error::Unknown(code) => code,
-            _ => i32::from(value),
-        }
+        value.code()
     }
 }
 
@@ -172,6 +217,7 @@ impl From<ffi::RpsResult> for Error {
             ffi::RpsResult_RPS_ERROR_NOT_SUPPORTED => Error::NotSupported,
             ffi::RpsResult_RPS_ERROR_RUNTIME_API_ERROR => Error::RuntimeApiError,
             ffi::RpsResult_RPS_ERROR_INTERNAL_ERROR => Error::InternalError,
+            i32::MIN => Error::CallbackPanicked,
             _ => Error::Unknown(value),
         }
     }
@@ -181,6 +227,8 @@ impl From<ffi::RpsResult> for Error {
 #[derive(Default)]
 pub struct DeviceBuilder {
     create_info: ffi::RpsDeviceCreateInfo,
+    printer: Option<Arc<PrintCallback>>,
+    allocator: Option<Arc<dyn Allocator>>,
 }
 
 impl DeviceBuilder {
@@ -200,40 +248,98 @@ impl DeviceBuilder {
         self
     }
 
+    /// Specify a closure to receive the diagnostic output of the device, one line at a time.
+    ///
+    /// This includes the details of validation failures and the dumps requested with diagnostic flags. With the `log`
+    /// feature enabled, the output is forwarded to the `log` crate under the `rps` target unless a printer is
+    /// specified.
+    pub fn printer(&mut self, printer: impl Fn(&str) + Send + Sync + 'static) -> &mut Self {
+        self.printer = Some(Arc::new(printer));
+        self
+    }
+
+    /// Specify the allocator of the CPU memory used by the device.
+    ///
+    /// Keep a clone of the [Arc] to query the allocator, e.g. a [allocator::CountingAllocator]. If no allocator is
+    /// specified, RPS uses its default allocator.
+    pub fn allocator(&mut self, allocator: Arc<dyn Allocator>) -> &mut Self {
+        self.allocator = Some(allocator);
+        self
+    }
+
     /// Creates a device object.
     pub fn build(&self) -> Result<Device, Error> {
+        let printer = self.create_printer();
+        let allocator = self.allocator.clone().map(Box::new);
+        let create_info = self.create_info(printer.as_deref(), allocator.as_ref());
+
         let mut handle = ptr::null_mut();
 
-        call!(ffi::rpsDeviceCreate(&self.create_info, &mut handle))?;
+        call!(ffi::rpsDeviceCreate(&create_info, &mut handle))?;
 
         Ok(Device {
             handle,
-            callbacks: None,
+            runtime: None,
+            printer,
+            allocator,
         })
     }
 
     /// Create a device with a dummy runtime.
-    pub fn build_null(&self, callbacks: Box<dyn runtime::Callbacks>) -> Result<Device, Error> {
-        let mut device = self.build()?;
-
+    ///
+    /// Keep a clone of `callbacks` to inspect the runtime state between frames.
+    pub fn build_null(&self, callbacks: Arc<dyn runtime::Callbacks>) -> Result<Device, Error> {
+        let printer = self.create_printer();
+        let allocator = self.allocator.clone().map(Box::new);
+        let device_create_info = self.create_info(printer.as_deref(), allocator.as_ref());
+
+        // Box the context to pass a thin pointer with a stable address as the user context.
+        let runtime = Box::new(runtime::RuntimeContext::new(callbacks));
         let runtime_info = ffi::RpsRuntimeDeviceCreateInfo {
-            pUserContext: callbacks.as_ref() as *const _ as *mut _,
+            pUserContext: runtime.as_ref() as *const runtime::RuntimeContext as *mut _,
             callbacks: runtime::CALLBACKS,
         };
 
         let create_info = ffi::RpsNullRuntimeDeviceCreateInfo {
-            pDeviceCreateInfo: &self.create_info,
+            pDeviceCreateInfo: &device_create_info,
             pRuntimeCreateInfo: &runtime_info,
         };
 
-        call!(ffi::rpsNullRuntimeDeviceCreate(
-            &create_info,
-            &mut device.handle
-        ))?;
+        let mut handle = ptr::null_mut();
+
+        call!(ffi::rpsNullRuntimeDeviceCreate(&create_info, &mut handle))?;
+
+        Ok(Device {
+            handle,
+            runtime: Some(runtime),
+            printer,
+            allocator,
+        })
+    }
+
+    fn create_printer(&self) -> Option<Box<Printer>> {
+        #[cfg(feature = "log")]
+        let default = Some(Arc::new(printer::log) as Arc<PrintCallback>);
+        #[cfg(not(feature = "log"))]
+        let default = None;
+
+        self.printer.clone().or(default).map(Printer::new)
+    }
 
-        device.callbacks = Some(callbacks);
+    fn create_info(
+        &self,
+        printer: Option<&Printer>,
+        allocator: Option<&AllocatorContext>,
+    ) -> ffi::RpsDeviceCreateInfo {
+        let mut create_info = self.create_info;
+        if let Some(printer) = printer {
+            create_info.printer = printer.raw();
+        }
+        if let Some(allocator) = allocator {
+            create_info.allocator = allocator::raw(allocator);
+        }
 
-        Ok(device)
+        create_info
     }
 }
 
@@ -241,16 +347,38 @@ impl DeviceBuilder {
 ///
 /// The RPS device is used as the main state object for the RPS runtime API. It provides a central location for data
 /// and callbacks of the rest of the software stack.
+///
+/// A device is [Send], so it can be moved to another thread along with its callbacks, printer and allocator, which
+/// are all required to be [Send] and [Sync]. It isn't [Sync], as RPS doesn't synchronize concurrent calls on a device
+/// and the objects created from it.
 pub struct Device {
     /// Raw handle.
     handle: ffi::RpsDevice,
-    /// Runtime callbacks (keep alive until the device is destroyed).
-    callbacks: Option<Box<dyn runtime::Callbacks>>,
+    /// Runtime callbacks and state (keep alive until the device is destroyed).
+    runtime: Option<Box<runtime::RuntimeContext>>,
+    /// Printer state (keep alive until the device is destroyed).
+    printer: Option<Box<Printer>>,
+    /// Allocator state (keep alive until the device is destroyed).
+    allocator: Option<AllocatorContext>,
 }
 
+impl Device {
+    /// Returns the debug names of the resources of the runtime, if it's implemented in Rust.
+    ///
+    /// The registry can be kept to look up names, e.g. for leak reports, after the device is destroyed.
+    pub fn debug_names(&self) -> Option<Arc<runtime::DebugNames>> {
+        self.runtime
+            .as_ref()
+            .map(|runtime| runtime.debug_names.clone())
+    }
+}
+
+// SAFETY: The device isn't bound to the thread it was created on, and all the state it shares with RPS is `Send`.
+unsafe impl Send for Device {}
+
 impl Drop for Device {
     fn drop(&mut self) {
-        unsafe { ffi::rpsDeviceDestroy(self.handle) };
+        guard::destroy(|| unsafe { ffi::rpsDeviceDestroy(self.handle) });
     }
 }
 
@@ -269,7 +397,93 @@ mod tests {
 
     #[test]
     fn null_runtime() {
-        let device = DeviceBuilder::new().build_null(Box::new(Dummy)).unwrap();
+        let device = DeviceBuilder::new().build_null(Arc::new(Dummy)).unwrap();
+        drop(device);
+    }
+
+    #[test]
+    fn shared_callbacks() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        #[derive(Default)]
+        struct State {
+            destroyed: AtomicBool,
+        }
+
+        impl runtime::Callbacks for State {
+            fn destroy_runtime(&self) {
+                self.destroyed.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let state = Arc::new(State::default());
+        let device = DeviceBuilder::new().build_null(state.clone()).unwrap();
+
+        // Devices can be moved to and dropped on another thread.
+        std::thread::spawn(move || drop(device)).join().unwrap();
+        assert!(state.destroyed.load(Ordering::Relaxed));
+    }
+
+    struct Panicking;
+    impl runtime::Callbacks for Panicking {
+        fn destroy_runtime(&self) {
+            panic!("destroy_runtime");
+        }
+    }
+
+    #[test]
+    fn callback_panic() {
+        let device = DeviceBuilder::new()
+            .build_null(Arc::new(Panicking))
+            .unwrap();
         drop(device);
+
+        // The panic while destroying the device is reported by the drop, not kept for unrelated calls.
+        assert!(guard::take().is_none());
+        DeviceBuilder::new().build().unwrap();
+    }
+
+    #[test]
+    fn printer() {
+        use std::sync::Mutex;
+
+        use graph::{DiagnosticFlags, RenderGraphCreateInfo, UpdateInfo};
+        use runtime::{AccessFlags, ResourceDesc};
+        use signature::{EntrySignature, NodeDesc, ParamDesc};
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let device = {
+            let lines = lines.clone();
+            DeviceBuilder::new()
+                .printer(move |line| lines.lock().unwrap().push(line.to_string()))
+                .build_null(Arc::new(Dummy))
+                .unwrap()
+        };
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "blit",
+            vec![ParamDesc::buffer("dst", AccessFlags::COPY_DEST)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("dst", 0, &ResourceDesc::buffer(64))?;
+            builder.add_node("blit", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+
+        // The schedule dump is printed through the device printer.
+        graph
+            .update(UpdateInfo::new(0).diagnostic_flags(DiagnosticFlags::POST_SCHEDULE_DUMP))
+            .unwrap();
+        drop(graph);
+        drop(device);
+
+        let lines = lines.lock().unwrap();
+        assert!(!lines.is_empty());
+        assert!(lines.iter().any(|line| line.contains("blit")));
     }
 }
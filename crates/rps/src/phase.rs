@@ -0,0 +1,282 @@
+//! Custom render graph phases implemented in Rust.
+
+use std::{cell::Cell, ptr};
+
+use crate::{
+    diagnostics::{Diagnostics, GraphRef},
+    graph::BatchLayout,
+    guard, Error,
+};
+use rps_sys as ffi;
+
+/// Phase of the render graph update pipeline, e.g. to lint a render graph or collect statistics.
+///
+/// Phases are added by [crate::runtime::Callbacks::build_render_graph_phases] when a render graph is created, run on
+/// every update of the render graph, and destroyed along with the render graph.
+pub trait RenderGraphPhase {
+    /// Runs the phase for the current update of the render graph.
+    fn run(&mut self, graph: &PhaseGraph) -> Result<(), Error>;
+
+    /// Invoked when the render graph is destroyed, before the phase is dropped.
+    fn destroy(&mut self) {}
+}
+
+/// Render graph state available to a running phase.
+pub struct PhaseGraph<'a> {
+    graph: GraphRef<'a>,
+}
+
+impl<'a> PhaseGraph<'a> {
+    /// Returns the raw render graph handle.
+    pub fn handle(&self) -> ffi::RpsRenderGraph {
+        self.graph.handle()
+    }
+
+    /// Returns the command batch layout, available once the render graph is scheduled.
+    pub fn batch_layout(&self) -> Result<BatchLayout, Error> {
+        self.graph.batch_layout()
+    }
+
+    /// Returns the diagnostic information of the resources and commands of the current update.
+    pub fn diagnostics(&self) -> Result<Diagnostics, Error> {
+        self.graph.diagnostics()
+    }
+}
+
+/// List of the phases to add to a render graph.
+#[derive(Default)]
+pub struct Phases {
+    phases: Vec<Box<dyn RenderGraphPhase>>,
+}
+
+impl Phases {
+    /// Appends a phase, run after the phases added before it.
+    pub fn push(&mut self, phase: impl RenderGraphPhase + 'static) -> &mut Self {
+        self.phases.push(Box::new(phase));
+        self
+    }
+
+    /// Returns the number of phases.
+    pub fn len(&self) -> usize {
+        self.phases.len()
+    }
+
+    /// Returns whether no phases were added.
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    /// Moves the phases into the storage of the render graph being created on this thread, returning the phase
+    /// infos to hand over to RPS.
+    ///
+    /// The infos stay in the storage until the render graph is destroyed, which runs [RenderGraphPhase::destroy].
+    pub(crate) fn attach(self) -> Result<(*const ffi::RpsRenderGraphPhaseInfo, u32), Error> {
+        // SAFETY: The storage is set by `PhaseStorage::collect` on this thread for the duration of the creation.
+        let storage =
+            unsafe { CREATING.with(Cell::get).as_mut() }.ok_or(Error::InvalidOperation)?;
+
+        for phase in self.phases {
+            // Box the trait object again to pass a thin pointer as the phase handle.
+            let mut phase = Box::new(phase);
+            storage.infos.push(ffi::RpsRenderGraphPhaseInfo {
+                hPhase: &mut *phase as *mut Box<dyn RenderGraphPhase> as ffi::RpsRenderGraphPhase,
+                pfnRun: Some(run_phase),
+                pfnDestroy: Some(destroy_phase),
+            });
+            storage.phases.push(phase);
+        }
+
+        Ok((storage.infos.as_ptr(), storage.infos.len() as u32))
+    }
+}
+
+/// Phases of a render graph, owned by the render graph and dropped after RPS destroyed it.
+///
+/// The phases are dropped by the storage rather than by RPS, so they are freed even if the creation of the render
+/// graph fails after the phases were added.
+#[derive(Default)]
+pub(crate) struct PhaseStorage {
+    phases: Vec<Box<Box<dyn RenderGraphPhase>>>,
+    infos: Vec<ffi::RpsRenderGraphPhaseInfo>,
+}
+
+impl PhaseStorage {
+    /// Runs the creation of a render graph, collecting the phases added by the runtime callbacks.
+    pub(crate) fn collect<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let outer = CREATING.with(|cell| cell.replace(self));
+        let result = f();
+        CREATING.with(|cell| cell.set(outer));
+        result
+    }
+}
+
+thread_local! {
+    /// Storage of the render graph being created on this thread.
+    static CREATING: Cell<*mut PhaseStorage> = Cell::new(ptr::null_mut());
+}
+
+unsafe fn get_phase<'a>(phase: ffi::RpsRenderGraphPhase) -> &'a mut Box<dyn RenderGraphPhase> {
+    &mut *(phase as *mut Box<dyn RenderGraphPhase>)
+}
+
+unsafe extern "C" fn run_phase(
+    render_graph: ffi::RpsRenderGraph,
+    phase: ffi::RpsRenderGraphPhase,
+) -> ffi::RpsResult {
+    let graph = PhaseGraph {
+        graph: GraphRef::new(render_graph),
+    };
+
+    guard::catch_result(|| get_phase(phase).run(&graph))
+}
+
+unsafe extern "C" fn destroy_phase(phase: ffi::RpsRenderGraphPhase) {
+    guard::catch((), || get_phase(phase).destroy());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        runtime::Callbacks,
+        signature::EntrySignature,
+        DeviceBuilder,
+    };
+
+    #[derive(Default)]
+    struct Stats {
+        runs: AtomicUsize,
+        cmds: AtomicUsize,
+        destroyed: AtomicUsize,
+        dropped: AtomicUsize,
+    }
+
+    struct StatsPhase(Arc<Stats>);
+
+    impl RenderGraphPhase for StatsPhase {
+        fn run(&mut self, graph: &PhaseGraph) -> Result<(), Error> {
+            let diagnostics = graph.diagnostics()?;
+            self.0.runs.fetch_add(1, Ordering::Relaxed);
+            self.0.cmds.store(diagnostics.cmds.len(), Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn destroy(&mut self) {
+            self.0.destroyed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    impl Drop for StatsPhase {
+        fn drop(&mut self) {
+            self.0.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    struct PhaseCallbacks {
+        stats: Arc<Stats>,
+        fail: bool,
+    }
+
+    impl Callbacks for PhaseCallbacks {
+        fn build_render_graph_phases(
+            &self,
+            _render_graph: ffi::RpsRenderGraph,
+            phases: &mut Phases,
+        ) -> Result<(), Error> {
+            phases.push(StatsPhase(self.stats.clone()));
+            match self.fail {
+                true => Err(Error::callback("no phases for you")),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_phase() {
+        let stats = Arc::new(Stats::default());
+        let device = DeviceBuilder::new()
+            .build_null(Arc::new(PhaseCallbacks {
+                stats: stats.clone(),
+                fail: false,
+            }))
+            .unwrap();
+
+        let mut graph = device
+            .create_render_graph(
+                RenderGraphCreateInfo::new().signature(EntrySignature::new("main")),
+            )
+            .unwrap();
+        graph.set_build_callback(|_| Ok(()));
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+        graph.update(&UpdateInfo::new(1)).unwrap();
+        assert_eq!(stats.runs.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            stats.cmds.load(Ordering::Relaxed),
+            graph.diagnostics().unwrap().cmds.len()
+        );
+
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 0);
+
+        drop(graph);
+        assert_eq!(stats.destroyed.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn failed_phase_creation() {
+        let stats = Arc::new(Stats::default());
+        let device = DeviceBuilder::new()
+            .build_null(Arc::new(PhaseCallbacks {
+                stats: stats.clone(),
+                fail: true,
+            }))
+            .unwrap();
+
+        assert!(matches!(
+            device.create_render_graph(
+                RenderGraphCreateInfo::new().signature(EntrySignature::new("main"))
+            ),
+            Err(Error::Callback(_))
+        ));
+        assert_eq!(stats.runs.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn failed_creation_after_attach() {
+        let stats = Arc::new(Stats::default());
+        let mut storage = PhaseStorage::default();
+
+        // Attach the phases like the runtime callback does, then fail the creation before RPS runs or destroys them.
+        let result = storage.collect(|| {
+            let mut phases = Phases::default();
+            phases.push(StatsPhase(stats.clone()));
+            let (infos, len) = phases.attach()?;
+            assert!(!infos.is_null());
+            assert_eq!(len, 1);
+            Err::<(), _>(Error::InvalidOperation)
+        });
+        assert!(matches!(result, Err(Error::InvalidOperation)));
+        assert!(CREATING.with(Cell::get).is_null());
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 0);
+
+        drop(storage);
+        assert_eq!(stats.runs.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.destroyed.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn attach_outside_creation() {
+        let mut phases = Phases::default();
+        phases.push(StatsPhase(Arc::default()));
+        assert!(matches!(phases.attach(), Err(Error::InvalidOperation)));
+    }
+}
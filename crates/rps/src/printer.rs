@@ -0,0 +1,178 @@
+//! Device printer forwarding RPS diagnostic output to Rust.
+
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+    sync::{Arc, Mutex},
+};
+
+use crate::guard;
+use rps_sys as ffi;
+
+/// Closure receiving the lines printed by RPS.
+pub(crate) type PrintCallback = dyn Fn(&str) + Send + Sync;
+
+/// Size of the buffer a single print call is formatted to, longer output is truncated.
+const FORMAT_BUFFER_SIZE: usize = 4096;
+
+extern "C" {
+    /// C runtime `vsnprintf`, taking the `va_list` type bindgen emits for the `pfnVPrintf` parameter.
+    ///
+    /// On x86_64 `va_list` is an array type which decays to a pointer to `__va_list_tag` in parameters, and bindgen emits
+    /// it as that pointer, so the list is forwarded the same way the C side passed it to the printer.
+    fn vsnprintf(buf: *mut c_char, size: usize, format: *const c_char, args: ffi::va_list)
+        -> c_int;
+}
+
+/// Printer state passed to RPS as the printer context.
+///
+/// RPS prints its output in fragments, so the fragments are collected and forwarded one line at a time.
+pub(crate) struct Printer {
+    callback: Arc<PrintCallback>,
+    line: Mutex<String>,
+}
+
+impl Printer {
+    pub(crate) fn new(callback: Arc<PrintCallback>) -> Box<Self> {
+        Box::new(Self {
+            callback,
+            line: Mutex::new(String::new()),
+        })
+    }
+
+    /// Returns the raw printer pointing to this printer state.
+    ///
+    /// The printer state must outlive the device the raw printer is passed to.
+    pub(crate) fn raw(&self) -> ffi::RpsPrinter {
+        ffi::RpsPrinter {
+            pContext: self as *const Self as *mut c_void,
+            pfnPrintf: None,
+            pfnVPrintf: Some(vprintf),
+        }
+    }
+
+    /// Appends a fragment of output, forwarding every completed line.
+    fn write(&self, text: &str) {
+        let mut line = self.line.lock().unwrap_or_else(|err| err.into_inner());
+        line.push_str(text);
+
+        while let Some(pos) = line.find('\n') {
+            let rest = line.split_off(pos + 1);
+            (self.callback)(line.trim_end_matches(&['\r', '\n'][..]));
+            *line = rest;
+        }
+    }
+
+    /// Forwards the pending incomplete line, if any.
+    pub(crate) fn flush(&self) {
+        let mut line = self.line.lock().unwrap_or_else(|err| err.into_inner());
+        if !line.is_empty() {
+            (self.callback)(&line);
+            line.clear();
+        }
+    }
+}
+
+impl Drop for Printer {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Printer callback formatting the output of RPS.
+unsafe extern "C" fn vprintf(context: *mut c_void, format: *const c_char, args: ffi::va_list) {
+    let printer = &*(context as *const Printer);
+
+    let mut buffer = [0 as c_char; FORMAT_BUFFER_SIZE];
+    if vsnprintf(buffer.as_mut_ptr(), buffer.len(), format, args) < 0 {
+        return;
+    }
+
+    let text = CStr::from_ptr(buffer.as_ptr()).to_string_lossy();
+    guard::catch((), || printer.write(&text));
+}
+
+/// Forwards a line of RPS output to the `log` crate.
+///
+/// RPS doesn't tag its output with a severity, so all lines are logged at the info level.
+#[cfg(feature = "log")]
+pub(crate) fn log(line: &str) {
+    log::info!(target: "rps", "{}", line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::{DiagnosticFlags, RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, AccessFlags, Callbacks, ResourceDesc},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    struct Dummy;
+    impl Callbacks for Dummy {}
+
+    #[test]
+    fn device_printer() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let device = {
+            let lines = lines.clone();
+            DeviceBuilder::new()
+                .printer(move |line| lines.lock().unwrap().push(line.to_string()))
+                .build_null(Arc::new(Dummy))
+                .unwrap()
+        };
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "upload_constants",
+            vec![ParamDesc::buffer("dst", AccessFlags::COPY_DEST)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let buffer =
+                builder.declare_resource("constant_buffer", 0, &ResourceDesc::buffer(256))?;
+            builder.add_node("upload_constants", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+
+        // RPS formats the node names into the dump, which only show up if the `va_list` is forwarded intact.
+        graph
+            .update(UpdateInfo::new(0).diagnostic_flags(DiagnosticFlags::POST_SCHEDULE_DUMP))
+            .unwrap();
+        drop(graph);
+        drop(device);
+
+        let lines = lines.lock().unwrap();
+        assert!(lines.iter().any(|line| line.contains("upload_constants")));
+        assert!(lines.iter().all(|line| !line.contains("%s")));
+    }
+
+    #[test]
+    fn split_lines() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+
+        let printer = {
+            let lines = lines.clone();
+            Printer::new(Arc::new(move |line: &str| {
+                lines.lock().unwrap().push(line.to_string())
+            }))
+        };
+
+        printer.write("Validation ");
+        printer.write("failed\r\nnode: ");
+        printer.write("blit\n\n");
+        printer.write("trailing");
+        assert_eq!(
+            *lines.lock().unwrap(),
+            ["Validation failed", "node: blit", ""]
+        );
+
+        drop(printer);
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "trailing");
+    }
+}
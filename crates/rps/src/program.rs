@@ -0,0 +1,172 @@
+//! Program wrappers and node callback binding.
+
+use std::{collections::HashMap, ffi::CString, marker::PhantomData, os::raw::c_void, ptr};
+
+use crate::{
+    call,
+    cmd::CmdContext,
+    guard,
+    signature::{EntrySignature, RawSignature},
+    Device, Error,
+};
+use rps_sys as ffi;
+
+/// Closure invoked to record the commands of a node.
+pub type NodeCallback<'a> = dyn Fn(&CmdContext) -> Result<(), Error> + 'a;
+
+/// Handle to an RPSL entry point of a compiled RPSL module.
+#[derive(Debug, Copy, Clone)]
+pub struct RpslEntry(ffi::RpsRpslEntry);
+
+impl RpslEntry {
+    /// Wraps a raw RPSL entry handle.
+    ///
+    /// # Safety
+    ///
+    /// `entry` must be a valid entry point of a compiled RPSL module, e.g. obtained with `rpsRpslDynamicLibraryInit`
+    /// or exported by a statically linked module.
+    pub unsafe fn from_raw(entry: ffi::RpsRpslEntry) -> Self {
+        Self(entry)
+    }
+
+    /// Returns the raw RPSL entry handle.
+    pub fn raw(&self) -> ffi::RpsRpslEntry {
+        self.0
+    }
+
+    /// Returns the signature of the entry, with its parameters and node declarations.
+    pub fn signature(&self) -> Result<EntrySignature, Error> {
+        let mut desc = ffi::RpsRenderGraphSignatureDesc::default();
+        call!(ffi::rpsRpslEntryGetSignatureDesc(self.0, &mut desc))?;
+
+        EntrySignature::try_from(&desc)
+    }
+}
+
+/// RPS program object.
+///
+/// A program is an instance of an RPSL entry or of a [EntrySignature] that node implementations can be bound to. Bound
+/// closures are kept alive until the program is dropped.
+pub struct Program<'a> {
+    /// Raw handle.
+    handle: ffi::RpsSubprogram,
+    /// Whether the program is owned and must be destroyed on drop.
+    owned: bool,
+    /// Bound node closures by node name.
+    nodes: HashMap<String, Box<Box<NodeCallback<'a>>>>,
+    /// Storage of the raw signature (keep alive until the program is destroyed).
+    _raw_signature: Option<RawSignature>,
+    _device: PhantomData<&'a Device>,
+}
+
+impl Device {
+    /// Creates a program from an RPSL entry point.
+    pub fn create_program(&self, entry: RpslEntry) -> Result<Program<'_>, Error> {
+        let create_info = ffi::RpsProgramCreateInfo {
+            hRpslEntryPoint: entry.raw(),
+            ..Default::default()
+        };
+
+        self.create_program_raw(&create_info, None)
+    }
+
+    /// Creates a program from a signature.
+    pub fn create_program_from_signature(
+        &self,
+        signature: &EntrySignature,
+    ) -> Result<Program<'_>, Error> {
+        let raw_signature = signature.to_raw()?;
+        let create_info = ffi::RpsProgramCreateInfo {
+            pSignatureDesc: raw_signature.desc(),
+            ..Default::default()
+        };
+
+        self.create_program_raw(&create_info, Some(raw_signature))
+    }
+
+    fn create_program_raw(
+        &self,
+        create_info: &ffi::RpsProgramCreateInfo,
+        raw_signature: Option<RawSignature>,
+    ) -> Result<Program<'_>, Error> {
+        let mut handle = ptr::null_mut();
+        call!(ffi::rpsProgramCreate(self.handle, create_info, &mut handle))?;
+
+        Ok(Program {
+            handle,
+            owned: true,
+            nodes: HashMap::new(),
+            _raw_signature: raw_signature,
+            _device: PhantomData,
+        })
+    }
+}
+
+impl<'a> Program<'a> {
+    /// Wraps the main entry program of a render graph, which is destroyed along with the render graph.
+    pub(crate) fn main_entry(handle: ffi::RpsSubprogram) -> Self {
+        Program {
+            handle,
+            owned: false,
+            nodes: HashMap::new(),
+            _raw_signature: None,
+            _device: PhantomData,
+        }
+    }
+
+    /// Returns the raw program handle.
+    pub fn handle(&self) -> ffi::RpsSubprogram {
+        self.handle
+    }
+
+    /// Binds a closure to record the commands of a node.
+    ///
+    /// Binding a closure to a node replaces the previously bound one.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the node declaration.
+    /// * `callback` - Closure to invoke for every instance of the node.
+    pub fn bind_node(
+        &mut self,
+        name: &str,
+        callback: impl Fn(&CmdContext) -> Result<(), Error> + 'a,
+    ) -> Result<(), Error> {
+        let c_name = CString::new(name).map_err(|_| Error::InvalidArguments)?;
+        let callback: Box<Box<NodeCallback<'a>>> = Box::new(Box::new(callback));
+
+        let cmd_callback = ffi::RpsCmdCallback {
+            pfnCallback: Some(node_callback),
+            pUserContext: callback.as_ref() as *const Box<NodeCallback> as *mut c_void,
+            ..Default::default()
+        };
+
+        call!(ffi::rpsProgramBindNodeCallback(
+            self.handle,
+            c_name.as_ptr(),
+            &cmd_callback
+        ))?;
+
+        self.nodes.insert(name.to_string(), callback);
+
+        Ok(())
+    }
+}
+
+impl<'a> Drop for Program<'a> {
+    fn drop(&mut self) {
+        if self.owned {
+            guard::destroy(|| unsafe { ffi::rpsProgramDestroy(self.handle) });
+        }
+    }
+}
+
+extern "C" fn node_callback(context: *const ffi::RpsCmdCallbackContext) {
+    let context = unsafe { CmdContext::from_raw(context) };
+    let callback = unsafe { &*(context.raw().pCmdCallbackContext as *const Box<NodeCallback>) };
+
+    let result = guard::catch_result(|| callback(&context));
+    if result != ffi::RpsResult_RPS_OK {
+        unsafe { ffi::rpsCmdCallbackReportError(context.raw(), result) };
+    }
+}
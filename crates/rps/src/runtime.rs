@@ -1,10 +1,30 @@
 //! Runtime API wrappers.
 
-use std::{ffi::CStr, fmt, os::raw::c_void, slice};
+use std::{
+    ffi::CStr,
+    fmt,
+    os::raw::{c_char, c_void},
+    slice,
+    sync::Arc,
+};
 
 use bitflags::bitflags;
-
-use crate::Error;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+mod host;
+mod names;
+mod recording;
+#[cfg(feature = "tracing")]
+mod trace;
+
+pub use host::HostRuntime;
+pub use names::DebugNames;
+pub use recording::{RecordingCallbacks, RuntimeOp};
+#[cfg(feature = "tracing")]
+pub use trace::TracingCallbacks;
+
+use crate::{guard, phase::Phases, Error};
 use rps_sys as ffi;
 
 bitflags! {
@@ -12,6 +32,7 @@ bitflags! {
     ///
     /// If specified for a node parameter, it indicates the required resource layout and synchronizations before entering
     /// and after exiting the node.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
     pub struct AccessFlags: i32 {
         /// Accessible as an indirect argument buffer.
         const INDIRECT_ARGS = ffi::RpsAccessFlagBits_RPS_ACCESS_INDIRECT_ARGS_BIT;
@@ -147,6 +168,7 @@ bitflags! {
 
 bitflags! {
     /// Bitflags for shader stages.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
     pub struct ShaderStage: u32 {
         /// Vertex shader stage.
         const VS = ffi::RpsShaderStageBits_RPS_SHADER_STAGE_VS;
@@ -173,7 +195,8 @@ bitflags! {
 
 /// Graphics resource and argument data usage semantics.
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Semantic {
     /// No semantics.
     Unspecified = ffi::RpsSemantic_RPS_SEMANTIC_UNSPECIFIED,
@@ -290,6 +313,60 @@ pub enum Semantic {
     UserResourceBinding = ffi::RpsSemantic_RPS_SEMANTIC_USER_RESOURCE_BINDING,
 }
 
+impl TryFrom<ffi::RpsSemantic> for Semantic {
+    type Error = Error;
+
+    fn try_from(value: ffi::RpsSemantic) -> Result<Self, Self::Error> {
+        let semantic = match value {
+            ffi::RpsSemantic_RPS_SEMANTIC_UNSPECIFIED => Semantic::Unspecified,
+            ffi::RpsSemantic_RPS_SEMANTIC_VERTEX_SHADER => Semantic::VertexShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_PIXEL_SHADER => Semantic::PixelShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_GEOMETRY_SHADER => Semantic::GeometryShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_COMPUTE_SHADER => Semantic::ComputeShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_HULL_SHADER => Semantic::HullShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_DOMAIN_SHADER => Semantic::DomainShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_RAYTRACING_PIPELINE => Semantic::RaytracingPipeline,
+            ffi::RpsSemantic_RPS_SEMANTIC_AMPLIFICATION_SHADER => Semantic::AmplificationShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_MESH_SHADER => Semantic::MeshShader,
+            ffi::RpsSemantic_RPS_SEMANTIC_VERTEX_LAYOUT => Semantic::VertexLayout,
+            ffi::RpsSemantic_RPS_SEMANTIC_STREAM_OUT_LAYOUT => Semantic::StreamOutLayout,
+            ffi::RpsSemantic_RPS_SEMANTIC_STREAM_OUT_DESC => Semantic::StreamOutDesc,
+            ffi::RpsSemantic_RPS_SEMANTIC_BLEND_STATE => Semantic::BlendState,
+            ffi::RpsSemantic_RPS_SEMANTIC_RENDER_TARGET_BLEND => Semantic::RenderTargetBlend,
+            ffi::RpsSemantic_RPS_SEMANTIC_DEPTH_STENCIL_STATE => Semantic::DepthStencilState,
+            ffi::RpsSemantic_RPS_SEMANTIC_RASTERIZER_STATE => Semantic::RasterizerState,
+            ffi::RpsSemantic_RPS_SEMANTIC_VIEWPORT => Semantic::Viewport,
+            ffi::RpsSemantic_RPS_SEMANTIC_SCISSOR => Semantic::Scissor,
+            ffi::RpsSemantic_RPS_SEMANTIC_PRIMITIVE_TOPOLOGY => Semantic::PrimitiveTopology,
+            ffi::RpsSemantic_RPS_SEMANTIC_PATCH_CONTROL_POINTS => Semantic::PatchControlPoints,
+            ffi::RpsSemantic_RPS_SEMANTIC_PRIMITIVE_STRIP_CUT_INDEX => {
+                Semantic::PrimitiveStripCutIndex
+            }
+            ffi::RpsSemantic_RPS_SEMANTIC_BLEND_FACTOR => Semantic::BlendFactor,
+            ffi::RpsSemantic_RPS_SEMANTIC_STENCIL_REF => Semantic::StencilRef,
+            ffi::RpsSemantic_RPS_SEMANTIC_DEPTH_BOUNDS => Semantic::DepthBounds,
+            ffi::RpsSemantic_RPS_SEMANTIC_SAMPLE_LOCATION => Semantic::SampleLocation,
+            ffi::RpsSemantic_RPS_SEMANTIC_SHADING_RATE => Semantic::ShadingRate,
+            ffi::RpsSemantic_RPS_SEMANTIC_COLOR_CLEAR_VALUE => Semantic::ColorClearValue,
+            ffi::RpsSemantic_RPS_SEMANTIC_DEPTH_CLEAR_VALUE => Semantic::DepthClearValue,
+            ffi::RpsSemantic_RPS_SEMANTIC_STENCIL_CLEAR_VALUE => Semantic::StencilClearValue,
+            ffi::RpsSemantic_RPS_SEMANTIC_VERTEX_BUFFER => Semantic::VertexBuffer,
+            ffi::RpsSemantic_RPS_SEMANTIC_INDEX_BUFFER => Semantic::IndexBuffer,
+            ffi::RpsSemantic_RPS_SEMANTIC_INDIRECT_ARGS => Semantic::IndirectArgs,
+            ffi::RpsSemantic_RPS_SEMANTIC_STREAM_OUT_BUFFER => Semantic::StreamOutBuffer,
+            ffi::RpsSemantic_RPS_SEMANTIC_INDIRECT_COUNT => Semantic::IndirectCount,
+            ffi::RpsSemantic_RPS_SEMANTIC_RENDER_TARGET => Semantic::RenderTarget,
+            ffi::RpsSemantic_RPS_SEMANTIC_DEPTH_STENCIL_TARGET => Semantic::DepthStencilTarget,
+            ffi::RpsSemantic_RPS_SEMANTIC_SHADING_RATE_IMAGE => Semantic::ShadingRateImage,
+            ffi::RpsSemantic_RPS_SEMANTIC_RESOLVE_TARGET => Semantic::ResolveTarget,
+            ffi::RpsSemantic_RPS_SEMANTIC_USER_RESOURCE_BINDING => Semantic::UserResourceBinding,
+            _ => return Err(Error::InvalidData),
+        };
+
+        Ok(semantic)
+    }
+}
+
 impl Semantic {
     /// Number of defined semantics.
     pub const COUNT: usize = ffi::RpsSemantic_RPS_SEMANTIC_COUNT as usize;
@@ -303,7 +380,8 @@ impl Semantic {
 
 /// Supported RPS formats.
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Format {
     /// Unknown format.
     Unknown = ffi::RpsFormat_RPS_FORMAT_UNKNOWN,
@@ -648,6 +726,134 @@ impl fmt::Display for Format {
     }
 }
 
+impl TryFrom<ffi::RpsFormat> for Format {
+    type Error = Error;
+
+    fn try_from(value: ffi::RpsFormat) -> Result<Self, Self::Error> {
+        let format = match value {
+            ffi::RpsFormat_RPS_FORMAT_UNKNOWN => Format::Unknown,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32A32_TYPELESS => Format::R32G32B32A32Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32A32_FLOAT => Format::R32G32B32A32Float,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32A32_UINT => Format::R32G32B32A32Uint,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32A32_SINT => Format::R32G32B32A32Sint,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32_TYPELESS => Format::R32G32B32Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32_FLOAT => Format::R32G32B32Float,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32_UINT => Format::R32G32B32Uint,
+            ffi::RpsFormat_RPS_FORMAT_R32G32B32_SINT => Format::R32G32B32Sint,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_TYPELESS => Format::R16G16B16A16Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_FLOAT => Format::R16G16B16A16Float,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_UNORM => Format::R16G16B16A16Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_UINT => Format::R16G16B16A16Uint,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_SNORM => Format::R16G16B16A16Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R16G16B16A16_SINT => Format::R16G16B16A16Sint,
+            ffi::RpsFormat_RPS_FORMAT_R32G32_TYPELESS => Format::R32G32Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R32G32_FLOAT => Format::R32G32Float,
+            ffi::RpsFormat_RPS_FORMAT_R32G32_UINT => Format::R32G32Uint,
+            ffi::RpsFormat_RPS_FORMAT_R32G32_SINT => Format::R32G32Sint,
+            ffi::RpsFormat_RPS_FORMAT_R32G8X24_TYPELESS => Format::R32G8X24Typeless,
+            ffi::RpsFormat_RPS_FORMAT_D32_FLOAT_S8X24_UINT => Format::D32FloatS8X24Uint,
+            ffi::RpsFormat_RPS_FORMAT_R32_FLOAT_X8X24_TYPELESS => Format::R32FloatX8X24Typeless,
+            ffi::RpsFormat_RPS_FORMAT_X32_TYPELESS_G8X24_UINT => Format::X32TypelessG8X24Uint,
+            ffi::RpsFormat_RPS_FORMAT_R10G10B10A2_TYPELESS => Format::R10G10B10A2Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R10G10B10A2_UNORM => Format::R10G10B10A2Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R10G10B10A2_UINT => Format::R10G10B10A2Uint,
+            ffi::RpsFormat_RPS_FORMAT_R11G11B10_FLOAT => Format::R11G11B10Float,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_TYPELESS => Format::R8G8B8A8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_UNORM => Format::R8G8B8A8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_UNORM_SRGB => Format::R8G8B8A8UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_UINT => Format::R8G8B8A8Uint,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_SNORM => Format::R8G8B8A8Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R8G8B8A8_SINT => Format::R8G8B8A8Sint,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_TYPELESS => Format::R16G16Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_FLOAT => Format::R16G16Float,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_UNORM => Format::R16G16Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_UINT => Format::R16G16Uint,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_SNORM => Format::R16G16Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R16G16_SINT => Format::R16G16Sint,
+            ffi::RpsFormat_RPS_FORMAT_R32_TYPELESS => Format::R32Typeless,
+            ffi::RpsFormat_RPS_FORMAT_D32_FLOAT => Format::D32Float,
+            ffi::RpsFormat_RPS_FORMAT_R32_FLOAT => Format::R32Float,
+            ffi::RpsFormat_RPS_FORMAT_R32_UINT => Format::R32Uint,
+            ffi::RpsFormat_RPS_FORMAT_R32_SINT => Format::R32Sint,
+            ffi::RpsFormat_RPS_FORMAT_R24G8_TYPELESS => Format::R24G8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_D24_UNORM_S8_UINT => Format::D24UnormS8Uint,
+            ffi::RpsFormat_RPS_FORMAT_R24_UNORM_X8_TYPELESS => Format::R24UnormX8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_X24_TYPELESS_G8_UINT => Format::X24TypelessG8Uint,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_TYPELESS => Format::R8G8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_UNORM => Format::R8G8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_UINT => Format::R8G8Uint,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_SNORM => Format::R8G8Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_SINT => Format::R8G8Sint,
+            ffi::RpsFormat_RPS_FORMAT_R16_TYPELESS => Format::R16Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R16_FLOAT => Format::R16Float,
+            ffi::RpsFormat_RPS_FORMAT_D16_UNORM => Format::D16Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R16_UNORM => Format::R16Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R16_UINT => Format::R16Uint,
+            ffi::RpsFormat_RPS_FORMAT_R16_SNORM => Format::R16Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R16_SINT => Format::R16Sint,
+            ffi::RpsFormat_RPS_FORMAT_R8_TYPELESS => Format::R8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_R8_UNORM => Format::R8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R8_UINT => Format::R8Uint,
+            ffi::RpsFormat_RPS_FORMAT_R8_SNORM => Format::R8Snorm,
+            ffi::RpsFormat_RPS_FORMAT_R8_SINT => Format::R8Sint,
+            ffi::RpsFormat_RPS_FORMAT_A8_UNORM => Format::A8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R1_UNORM => Format::R1Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R9G9B9E5_SHAREDEXP => Format::R9G9B9E5Sharedexp,
+            ffi::RpsFormat_RPS_FORMAT_R8G8_B8G8_UNORM => Format::R8G8B8G8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_G8R8_G8B8_UNORM => Format::G8R8G8B8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC1_TYPELESS => Format::BC1Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC1_UNORM => Format::BC1Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC1_UNORM_SRGB => Format::BC1UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_BC2_TYPELESS => Format::BC2Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC2_UNORM => Format::BC2Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC2_UNORM_SRGB => Format::BC2UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_BC3_TYPELESS => Format::BC3Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC3_UNORM => Format::BC3Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC3_UNORM_SRGB => Format::BC3UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_BC4_TYPELESS => Format::BC4Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC4_UNORM => Format::BC4Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC4_SNORM => Format::BC4Snorm,
+            ffi::RpsFormat_RPS_FORMAT_BC5_TYPELESS => Format::BC5Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC5_UNORM => Format::BC5Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC5_SNORM => Format::BC5Snorm,
+            ffi::RpsFormat_RPS_FORMAT_B5G6R5_UNORM => Format::B5G6R5Unorm,
+            ffi::RpsFormat_RPS_FORMAT_B5G5R5A1_UNORM => Format::B5G5R5A1Unorm,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8A8_UNORM => Format::B8G8R8A8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8X8_UNORM => Format::B8G8R8X8Unorm,
+            ffi::RpsFormat_RPS_FORMAT_R10G10B10_XR_BIAS_A2_UNORM => Format::R10G10B10XrBiasA2Unorm,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8A8_TYPELESS => Format::B8G8R8A8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8A8_UNORM_SRGB => Format::B8G8R8A8UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8X8_TYPELESS => Format::B8G8R8X8Typeless,
+            ffi::RpsFormat_RPS_FORMAT_B8G8R8X8_UNORM_SRGB => Format::B8G8R8X8UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_BC6H_TYPELESS => Format::BC6HTypeless,
+            ffi::RpsFormat_RPS_FORMAT_BC6H_UF16 => Format::BC6HUF16,
+            ffi::RpsFormat_RPS_FORMAT_BC6H_SF16 => Format::BC6HSF16,
+            ffi::RpsFormat_RPS_FORMAT_BC7_TYPELESS => Format::BC7Typeless,
+            ffi::RpsFormat_RPS_FORMAT_BC7_UNORM => Format::BC7Unorm,
+            ffi::RpsFormat_RPS_FORMAT_BC7_UNORM_SRGB => Format::BC7UnormSrgb,
+            ffi::RpsFormat_RPS_FORMAT_AYUV => Format::AYUV,
+            ffi::RpsFormat_RPS_FORMAT_Y410 => Format::Y410,
+            ffi::RpsFormat_RPS_FORMAT_Y416 => Format::Y416,
+            ffi::RpsFormat_RPS_FORMAT_NV12 => Format::NV12,
+            ffi::RpsFormat_RPS_FORMAT_P010 => Format::P010,
+            ffi::RpsFormat_RPS_FORMAT_P016 => Format::P016,
+            ffi::RpsFormat_RPS_FORMAT_420_OPAQUE => Format::Opaque420,
+            ffi::RpsFormat_RPS_FORMAT_YUY2 => Format::YUY2,
+            ffi::RpsFormat_RPS_FORMAT_Y210 => Format::Y210,
+            ffi::RpsFormat_RPS_FORMAT_Y216 => Format::Y216,
+            ffi::RpsFormat_RPS_FORMAT_NV11 => Format::NV11,
+            ffi::RpsFormat_RPS_FORMAT_AI44 => Format::AI44,
+            ffi::RpsFormat_RPS_FORMAT_IA44 => Format::IA44,
+            ffi::RpsFormat_RPS_FORMAT_P8 => Format::P8,
+            ffi::RpsFormat_RPS_FORMAT_A8P8 => Format::A8P8,
+            ffi::RpsFormat_RPS_FORMAT_B4G4R4A4_UNORM => Format::B4G4R4A4Unorm,
+            _ => return Err(Error::InvalidData),
+        };
+
+        Ok(format)
+    }
+}
+
 impl Format {
     /// Number of formats available.
     pub const COUNT: usize = ffi::RpsFormat_RPS_FORMAT_COUNT as usize;
@@ -701,19 +907,613 @@ pub type Viewport = ffi::RpsViewport;
 /// Rectangular geometrical figure.
 pub type Rect = ffi::RpsRect;
 
+/// Serde definition of [Viewport], for use with `#[serde(with = "rps::runtime::ViewportDef")]`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Viewport")]
+#[allow(non_snake_case)]
+pub struct ViewportDef {
+    /// Left coordinate.
+    pub x: f32,
+    /// Top coordinate.
+    pub y: f32,
+    /// Width.
+    pub width: f32,
+    /// Height.
+    pub height: f32,
+    /// Minimum depth.
+    pub minZ: f32,
+    /// Maximum depth.
+    pub maxZ: f32,
+}
+
+/// Serde definition of [Rect], for use with `#[serde(with = "rps::runtime::RectDef")]`.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rect")]
+pub struct RectDef {
+    /// Left coordinate.
+    pub x: i32,
+    /// Top coordinate.
+    pub y: i32,
+    /// Width.
+    pub width: i32,
+    /// Height.
+    pub height: i32,
+}
+
 /// Primitive topology types.
 ///
 /// This maps to common API primitive topology enumerations
 /// such as `D3D_PRIMITIVE_TOPOLOGY` and `VkPrimitiveTopology`.
 pub type PrimitiveTopology = ffi::RpsPrimitiveTopology;
 
+/// Resource identifier within a render graph.
+pub type ResourceId = ffi::RpsResourceId;
+
+/// Runtime specific resource handle, e.g. a `VkImage`.
+pub type RuntimeResource = ffi::RpsRuntimeResource;
+
+/// Runtime heap handle.
+pub type RuntimeHeap = ffi::RpsRuntimeHeap;
+
+/// Image view of a resource.
+pub type ImageView = ffi::RpsImageView;
+
+/// Buffer view of a resource.
+pub type BufferView = ffi::RpsBufferView;
+
+/// Resource types used by RPS resources.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ResourceType {
+    /// Resource type is unknown / invalid.
+    Unknown = ffi::RpsResourceType_RPS_RESOURCE_TYPE_UNKNOWN,
+    /// A buffer resource type.
+    Buffer = ffi::RpsResourceType_RPS_RESOURCE_TYPE_BUFFER,
+    /// A 1D image resource type.
+    Image1D = ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_1D,
+    /// A 2D image resource type.
+    Image2D = ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_2D,
+    /// A 3D image resource type.
+    Image3D = ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_3D,
+}
+
+impl TryFrom<ffi::RpsResourceType> for ResourceType {
+    type Error = Error;
+
+    fn try_from(value: ffi::RpsResourceType) -> Result<Self, Self::Error> {
+        Ok(match value {
+            ffi::RpsResourceType_RPS_RESOURCE_TYPE_UNKNOWN => ResourceType::Unknown,
+            ffi::RpsResourceType_RPS_RESOURCE_TYPE_BUFFER => ResourceType::Buffer,
+            ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_1D => ResourceType::Image1D,
+            ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_2D => ResourceType::Image2D,
+            ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_3D => ResourceType::Image3D,
+            _ => return Err(Error::InvalidData),
+        })
+    }
+}
+
+bitflags! {
+    /// Bitflags for special properties of a resource.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+    pub struct ResourceFlags: u32 {
+        /// Supports cubemap views.
+        const CUBEMAP_COMPATIBLE = ffi::RpsResourceFlagBits_RPS_RESOURCE_CUBEMAP_COMPATIBLE_BIT;
+        /// Uses row-major image layout.
+        const ROWMAJOR_IMAGE = ffi::RpsResourceFlagBits_RPS_RESOURCE_ROWMAJOR_IMAGE_BIT;
+        /// Preferred to be in GPU-local CPU-visible heap if available.
+        const PREFER_GPU_LOCAL_CPU_VISIBLE = ffi::RpsResourceFlagBits_RPS_RESOURCE_PREFER_GPU_LOCAL_CPU_VISIBLE_BIT;
+        /// Preferred to be in dedicated allocation or as committed resource.
+        const PREFER_DEDICATED_ALLOCATION = ffi::RpsResourceFlagBits_RPS_RESOURCE_PREFER_DEDICATED_ALLOCATION_BIT;
+        /// Resource data is persistent from frame to frame.
+        const PERSISTENT = ffi::RpsResourceFlagBits_RPS_RESOURCE_PERSISTENT_BIT;
+    }
+}
+
+/// Resource description.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourceDesc {
+    /// Resource type.
+    pub ty: ResourceType,
+    /// Number of temporal layers the resource consists of.
+    pub temporal_layers: u32,
+    /// Special properties of the resource.
+    pub flags: ResourceFlags,
+    /// Width of an image, or size of a buffer in bytes.
+    pub width: u64,
+    /// Height of an image. Ignored for buffers.
+    pub height: u32,
+    /// Depth of a 3D image or number of array layers of other images. Ignored for buffers.
+    pub depth_or_array_layers: u32,
+    /// Number of mipmap levels of an image. Ignored for buffers.
+    pub mip_levels: u32,
+    /// Image format. Ignored for buffers.
+    pub format: Format,
+    /// Number of MSAA samples of an image. Ignored for buffers.
+    pub sample_count: u32,
+}
+
+impl ResourceDesc {
+    /// Creates a description of a buffer.
+    pub fn buffer(size: u64) -> Self {
+        Self {
+            ty: ResourceType::Buffer,
+            temporal_layers: 1,
+            flags: ResourceFlags::empty(),
+            width: size,
+            height: 1,
+            depth_or_array_layers: 1,
+            mip_levels: 1,
+            format: Format::Unknown,
+            sample_count: 1,
+        }
+    }
+
+    /// Creates a description of a single sampled 2D image without mipmaps.
+    pub fn image_2d(format: Format, width: u32, height: u32) -> Self {
+        Self {
+            ty: ResourceType::Image2D,
+            width: u64::from(width),
+            height,
+            format,
+            ..Self::buffer(0)
+        }
+    }
+
+    /// Returns whether the resource is a buffer.
+    #[inline]
+    pub fn is_buffer(&self) -> bool {
+        self.ty == ResourceType::Buffer
+    }
+}
+
+impl From<&ResourceDesc> for ffi::RpsResourceDesc {
+    fn from(value: &ResourceDesc) -> Self {
+        let mut desc = ffi::RpsResourceDesc {
+            type_: value.ty as ffi::RpsResourceType,
+            temporalLayers: value.temporal_layers,
+            flags: value.flags.bits(),
+            ..Default::default()
+        };
+
+        unsafe {
+            if value.is_buffer() {
+                let buffer = &mut desc.__bindgen_anon_1.buffer;
+                buffer.sizeInBytesLo = value.width as u32;
+                buffer.sizeInBytesHi = (value.width >> 32) as u32;
+            } else {
+                let image = &mut desc.__bindgen_anon_1.image;
+                image.width = value.width as u32;
+                image.height = value.height;
+                image.__bindgen_anon_1.depth = value.depth_or_array_layers;
+                image.mipLevels = value.mip_levels;
+                image.format = value.format as ffi::RpsFormat;
+                image.sampleCount = value.sample_count;
+            }
+        }
+
+        desc
+    }
+}
+
+impl TryFrom<&ffi::RpsResourceDesc> for ResourceDesc {
+    type Error = Error;
+
+    fn try_from(value: &ffi::RpsResourceDesc) -> Result<Self, Self::Error> {
+        let ty = ResourceType::try_from(value.type_)?;
+        let flags = ResourceFlags::from_bits_truncate(value.flags);
+
+        let desc = unsafe {
+            if ty == ResourceType::Buffer {
+                let buffer = &value.__bindgen_anon_1.buffer;
+                Self {
+                    temporal_layers: value.temporalLayers,
+                    flags,
+                    ..Self::buffer(
+                        u64::from(buffer.sizeInBytesLo) | u64::from(buffer.sizeInBytesHi) << 32,
+                    )
+                }
+            } else {
+                let image = &value.__bindgen_anon_1.image;
+                Self {
+                    ty,
+                    temporal_layers: value.temporalLayers,
+                    flags,
+                    width: u64::from(image.width),
+                    height: image.height,
+                    depth_or_array_layers: image.__bindgen_anon_1.depth,
+                    mip_levels: image.mipLevels,
+                    format: Format::try_from(image.format)?,
+                    sample_count: image.sampleCount,
+                }
+            }
+        };
+
+        Ok(desc)
+    }
+}
+
+/// Creates a default view of a whole image resource.
+pub fn image_view(resource_id: ResourceId) -> ImageView {
+    ImageView {
+        base: ffi::RpsResourceView {
+            resourceId: resource_id,
+            ..Default::default()
+        },
+        subresourceRange: ffi::RpsSubresourceRange {
+            baseMipLevel: 0,
+            mipLevels: 1,
+            baseArrayLayer: 0,
+            arrayLayers: 1,
+        },
+        // RPS_IMAGE_VIEW_COMPONENT_MAPPING_DEFAULT (identity RGBA swizzle).
+        componentMapping: 0x0302_0100,
+        ..Default::default()
+    }
+}
+
+/// Creates a default view of a whole buffer resource.
+pub fn buffer_view(resource_id: ResourceId) -> BufferView {
+    BufferView {
+        base: ffi::RpsResourceView {
+            resourceId: resource_id,
+            ..Default::default()
+        },
+        offset: 0,
+        // RPS_BUFFER_WHOLE_SIZE.
+        sizeInBytes: u64::MAX,
+        ..Default::default()
+    }
+}
+
+/// Clear value of a resource, interpreted according to the resource format.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearValue {
+    /// Clear color of a color image.
+    Color([f32; 4]),
+    /// Clear values of a depth stencil image.
+    DepthStencil {
+        /// Depth clear value.
+        depth: f32,
+        /// Stencil clear value.
+        stencil: u32,
+    },
+}
+
+/// Memory requirement of a resource.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryRequirement {
+    /// Size in bytes.
+    pub size: u64,
+    /// Alignment in bytes.
+    pub alignment: u32,
+    /// Index of the memory type.
+    pub memory_type_index: u32,
+}
+
+impl From<&ffi::RpsGpuMemoryRequirement> for MemoryRequirement {
+    fn from(value: &ffi::RpsGpuMemoryRequirement) -> Self {
+        Self {
+            size: value.size,
+            alignment: value.alignment,
+            memory_type_index: value.memoryTypeIndex,
+        }
+    }
+}
+
+/// Placement of a resource in a heap.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeapPlacement {
+    /// Heap the resource is placed in, null for a dedicated allocation.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub heap: RuntimeHeap,
+    /// Offset of the resource in the heap in bytes.
+    pub offset: u64,
+}
+
+/// Kind of a debug marker.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugMarkerMode {
+    /// Begins a debug marker region.
+    Begin,
+    /// Inserts a single debug marker label.
+    Label,
+    /// Ends the current debug marker region.
+    End,
+}
+
+/// Arguments of [Callbacks::create_heap].
+pub struct CreateHeapArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpCreateHeapArgs,
+}
+
+impl<'a> CreateHeapArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpCreateHeapArgs {
+        self.raw
+    }
+
+    /// Returns the size of the heap in bytes.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.raw.size
+    }
+
+    /// Returns the alignment of the heap in bytes.
+    #[inline]
+    pub fn alignment(&self) -> u32 {
+        self.raw.alignment
+    }
+
+    /// Returns the index of the memory type of the heap.
+    #[inline]
+    pub fn memory_type_index(&self) -> u32 {
+        self.raw.memoryTypeIndex
+    }
+
+    /// Returns the debug name of the heap.
+    pub fn debug_name(&self) -> Result<&'a str, Error> {
+        c_str(self.raw.debugName)
+    }
+
+    /// Sets the created heap.
+    pub fn set_heap(&mut self, heap: RuntimeHeap) {
+        if !self.raw.phHeap.is_null() {
+            unsafe { *self.raw.phHeap = heap };
+        }
+    }
+}
+
+/// Arguments of [Callbacks::destroy_heap].
+pub struct DestroyHeapArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpDestroyHeapArgs,
+}
+
+impl<'a> DestroyHeapArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpDestroyHeapArgs {
+        self.raw
+    }
+
+    /// Returns the heaps to destroy.
+    pub fn heaps(&self) -> &'a [RuntimeHeap] {
+        raw_slice(self.raw.phHeaps, self.raw.numHeaps)
+    }
+}
+
+/// Arguments of [Callbacks::create_resource].
+pub struct CreateResourceArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpCreateResourceArgs,
+}
+
+impl<'a> CreateResourceArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpCreateResourceArgs {
+        self.raw
+    }
+
+    /// Returns the identifier of the resource in the render graph.
+    #[inline]
+    pub fn resource_id(&self) -> ResourceId {
+        self.raw.resourceIndex
+    }
+
+    /// Returns the description of the resource.
+    pub fn desc(&self) -> Result<ResourceDesc, Error> {
+        ResourceDesc::try_from(&self.raw.desc)
+    }
+
+    /// Returns the clear value of the resource, interpreted according to its format.
+    pub fn clear_value(&self) -> Result<ClearValue, Error> {
+        let desc = self.desc()?;
+        let value = &self.raw.originalClearValue;
+
+        let value = unsafe {
+            if !desc.is_buffer() && desc.format.has_depth_stencil() {
+                ClearValue::DepthStencil {
+                    depth: value.depthStencil.depth,
+                    stencil: value.depthStencil.stencil,
+                }
+            } else {
+                ClearValue::Color(value.color.float32)
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Returns the memory requirement of the resource.
+    #[inline]
+    pub fn memory_requirement(&self) -> MemoryRequirement {
+        MemoryRequirement::from(&self.raw.allocRequirement)
+    }
+
+    /// Returns the placement of the resource in a heap.
+    #[inline]
+    pub fn placement(&self) -> HeapPlacement {
+        HeapPlacement {
+            heap: self.raw.allocPlacement.hHeap,
+            offset: self.raw.allocPlacement.offset,
+        }
+    }
+
+    /// Returns the union of all accesses to the resource.
+    #[inline]
+    pub fn all_accesses(&self) -> AccessFlags {
+        AccessFlags::from_bits_truncate(self.raw.allAccesses.accessFlags as i32)
+    }
+
+    /// Returns the initial access of the resource.
+    #[inline]
+    pub fn initial_access(&self) -> AccessFlags {
+        AccessFlags::from_bits_truncate(self.raw.initialAccess.accessFlags as i32)
+    }
+
+    /// Sets the created resource.
+    pub fn set_resource(&mut self, resource: RuntimeResource) {
+        if !self.raw.phResource.is_null() {
+            unsafe { *self.raw.phResource = resource };
+        }
+    }
+}
+
+/// Arguments of [Callbacks::destroy_resource].
+pub struct DestroyResourceArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpDestroyResourceArgs,
+}
+
+impl<'a> DestroyResourceArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpDestroyResourceArgs {
+        self.raw
+    }
+
+    /// Returns the type of the resources to destroy.
+    pub fn resource_type(&self) -> Result<ResourceType, Error> {
+        ResourceType::try_from(self.raw.resourceType)
+    }
+
+    /// Returns the resources to destroy.
+    pub fn resources(&self) -> &'a [RuntimeResource] {
+        raw_slice(self.raw.phResources, self.raw.numResources)
+    }
+}
+
+/// Arguments of [Callbacks::create_node_resources].
+pub struct CreateNodeResourcesArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpCreateNodeUserResourcesArgs,
+}
+
+impl<'a> CreateNodeResourcesArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpCreateNodeUserResourcesArgs {
+        self.raw
+    }
+
+    /// Returns the user context of the node.
+    #[inline]
+    pub fn user_context(&self) -> *mut c_void {
+        self.raw.pUserContext
+    }
+
+    /// Returns the pointers to the node arguments.
+    pub fn args(&self) -> &'a [ffi::RpsVariable] {
+        raw_slice(self.raw.ppArgs, self.raw.numArgs)
+    }
+
+    /// Returns the user tag of the node.
+    #[inline]
+    pub fn node_tag(&self) -> u32 {
+        self.raw.nodeTag
+    }
+}
+
+/// Arguments of [Callbacks::record_debug_marker].
+pub struct RecordDebugMarkerArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpRecordDebugMarkerArgs,
+}
+
+impl<'a> RecordDebugMarkerArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpRecordDebugMarkerArgs {
+        self.raw
+    }
+
+    /// Returns the command buffer to record the marker to.
+    #[inline]
+    pub fn command_buffer(&self) -> ffi::RpsRuntimeCommandBuffer {
+        self.raw.hCommandBuffer
+    }
+
+    /// Returns the user context passed to the record call.
+    #[inline]
+    pub fn user_record_context(&self) -> *mut c_void {
+        self.raw.pUserRecordContext
+    }
+
+    /// Returns the kind of the marker.
+    pub fn mode(&self) -> Result<DebugMarkerMode, Error> {
+        Ok(match self.raw.mode {
+            ffi::RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_BEGIN => DebugMarkerMode::Begin,
+            ffi::RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_LABEL => DebugMarkerMode::Label,
+            ffi::RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_END => DebugMarkerMode::End,
+            _ => return Err(Error::InvalidData),
+        })
+    }
+
+    /// Returns the text of the marker, empty when ending a region.
+    pub fn text(&self) -> Result<&'a str, Error> {
+        c_str(self.raw.text)
+    }
+}
+
+/// Arguments of [Callbacks::set_debug_name].
+pub struct SetDebugNameArgs<'a> {
+    raw: &'a ffi::RpsRuntimeOpSetDebugNameArgs,
+}
+
+impl<'a> SetDebugNameArgs<'a> {
+    /// Returns the raw arguments.
+    pub fn raw(&self) -> &'a ffi::RpsRuntimeOpSetDebugNameArgs {
+        self.raw
+    }
+
+    /// Returns the resource to name.
+    #[inline]
+    pub fn resource(&self) -> RuntimeResource {
+        self.raw.hResource
+    }
+
+    /// Returns the type of the resource to name.
+    pub fn resource_type(&self) -> Result<ResourceType, Error> {
+        ResourceType::try_from(self.raw.resourceType)
+    }
+
+    /// Returns the debug name.
+    pub fn name(&self) -> Result<&'a str, Error> {
+        c_str(self.raw.name)
+    }
+}
+
+fn c_str<'a>(ptr: *const c_char) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Ok("");
+    }
+
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| Error::InvalidData)
+}
+
+fn raw_slice<'a, T>(ptr: *const T, len: u32) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        return &[];
+    }
+
+    unsafe { slice::from_raw_parts(ptr, len as usize) }
+}
+
 /// Callback functions of a runtime.
-pub trait Callbacks {
-    /// Render graph phase build callback.
+///
+/// Callbacks are shared with the device as an `Arc`, so the application can keep a handle to inspect their state.
+/// State mutated by the callbacks needs interior mutability, e.g. a `Mutex`, as callbacks may run on whichever thread
+/// the device was moved to.
+///
+/// Failing callbacks can return [Error::callback] to preserve their own error, which is then returned as the source
+/// of the error of the API call invoking the callback.
+pub trait Callbacks: Send + Sync {
+    /// Render graph phase build callback, invoked when a render graph is created.
+    ///
+    /// Phases pushed to `phases` are run on every update of the render graph, see [crate::phase::RenderGraphPhase].
     fn build_render_graph_phases(
         &self,
-        _render_graph: RenderGraph,
-        _phase_info: &[*const ffi::RpsRenderGraphPhaseInfo],
+        _render_graph: ffi::RpsRenderGraph,
+        _phases: &mut Phases,
     ) -> Result<(), Error> {
         Ok(())
     }
@@ -722,26 +1522,23 @@ pub trait Callbacks {
     fn destroy_runtime(&self) {}
 
     /// Heap creation callback.
-    fn create_heap(&self, _args: &ffi::RpsRuntimeOpCreateHeapArgs) -> Result<(), Error> {
+    fn create_heap(&self, _args: &mut CreateHeapArgs) -> Result<(), Error> {
         Ok(())
     }
 
     /// Heap destruction callback.
-    fn destroy_heap(&self, _args: &ffi::RpsRuntimeOpDestroyHeapArgs) {}
+    fn destroy_heap(&self, _args: &DestroyHeapArgs) {}
 
     /// Resource creation callback.
-    fn create_resource(&self, _args: &ffi::RpsRuntimeOpCreateResourceArgs) -> Result<(), Error> {
+    fn create_resource(&self, _args: &mut CreateResourceArgs) -> Result<(), Error> {
         Ok(())
     }
 
     /// Resource destruction callback.
-    fn destroy_resource(&self, _args: &ffi::RpsRuntimeOpDestroyResourceArgs) {}
+    fn destroy_resource(&self, _args: &DestroyResourceArgs) {}
 
     /// Node resource creation callback.
-    fn create_node_resources(
-        &self,
-        _args: &ffi::RpsRuntimeOpCreateNodeUserResourcesArgs,
-    ) -> Result<(), Error> {
+    fn create_node_resources(&self, _args: &CreateNodeResourcesArgs) -> Result<(), Error> {
         Ok(())
     }
 
@@ -749,105 +1546,149 @@ pub trait Callbacks {
     fn destroy_node_resources(&self) {}
 
     /// Debug marker recording callback.
-    fn record_debug_marker(&self, _args: &ffi::RpsRuntimeOpRecordDebugMarkerArgs) {}
+    fn record_debug_marker(&self, _args: &RecordDebugMarkerArgs) {}
 
     /// Debug name setting callback.
-    fn set_debug_name(&self, _args: &ffi::RpsRuntimeOpSetDebugNameArgs) {}
+    fn set_debug_name(&self, _args: &SetDebugNameArgs) {}
 }
 
-pub type RenderGraph = ffi::RpsRenderGraph;
-
 extern "C" fn build_render_graph_phases(
     user_data: *mut c_void,
     render_graph: ffi::RpsRenderGraph,
     phase_info: *mut *const ffi::RpsRenderGraphPhaseInfo,
     num_phases: *mut u32,
 ) -> ffi::RpsResult {
-    let phase_info: &[*const ffi::RpsRenderGraphPhaseInfo] =
-        unsafe { slice::from_raw_parts(phase_info, *num_phases as usize) };
+    guard::catch_result(|| {
+        let mut phases = Phases::default();
+        get_callbacks(user_data).build_render_graph_phases(render_graph, &mut phases)?;
 
-    let result = get_callbacks(user_data).build_render_graph_phases(render_graph, phase_info);
-    match result {
-        Ok(()) => ffi::RpsResult_RPS_OK,
-        Err(err) => err.into(),
-    }
+        let (infos, len) = phases.attach()?;
+        unsafe {
+            *phase_info = infos;
+            *num_phases = len;
+        }
+
+        Ok(())
+    })
 }
 
 extern "C" fn destroy_runtime(user_data: *mut c_void) {
-    get_callbacks(user_data).destroy_runtime();
+    guard::catch((), || get_callbacks(user_data).destroy_runtime());
 }
 
 extern "C" fn create_heap(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpCreateHeapArgs,
 ) -> ffi::RpsResult {
-    let args = unsafe { &*args };
-    let result = get_callbacks(user_data).create_heap(args);
-    match result {
-        Ok(()) => ffi::RpsResult_RPS_OK,
-        Err(err) => err.into(),
-    }
+    guard::catch_result(|| {
+        get_callbacks(user_data).create_heap(&mut CreateHeapArgs {
+            raw: unsafe { &*args },
+        })
+    })
 }
 
 extern "C" fn destroy_heap(user_data: *mut c_void, args: *const ffi::RpsRuntimeOpDestroyHeapArgs) {
-    let args = unsafe { &*args };
-    get_callbacks(user_data).destroy_heap(args);
+    guard::catch((), || {
+        get_callbacks(user_data).destroy_heap(&DestroyHeapArgs {
+            raw: unsafe { &*args },
+        })
+    });
 }
 
 extern "C" fn create_resource(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpCreateResourceArgs,
 ) -> ffi::RpsResult {
-    let args = unsafe { &*args };
-    let result = get_callbacks(user_data).create_resource(args);
-    match result {
-        Ok(()) => ffi::RpsResult_RPS_OK,
-        Err(err) => err.into(),
-    }
+    guard::catch_result(|| {
+        get_callbacks(user_data).create_resource(&mut CreateResourceArgs {
+            raw: unsafe { &*args },
+        })
+    })
 }
 
 extern "C" fn destroy_resource(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpDestroyResourceArgs,
 ) {
-    let args = unsafe { &*args };
-    get_callbacks(user_data).destroy_resource(args);
+    guard::catch((), || {
+        let context = get_context(user_data);
+        let args = DestroyResourceArgs {
+            raw: unsafe { &*args },
+        };
+
+        context.debug_names.remove(args.resources());
+        context.callbacks.destroy_resource(&args);
+    });
 }
 
 extern "C" fn create_node_resources(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpCreateNodeUserResourcesArgs,
 ) -> ffi::RpsResult {
-    let args = unsafe { &*args };
-    let result = get_callbacks(user_data).create_node_resources(args);
-    match result {
-        Ok(()) => ffi::RpsResult_RPS_OK,
-        Err(err) => err.into(),
-    }
+    guard::catch_result(|| {
+        get_callbacks(user_data).create_node_resources(&CreateNodeResourcesArgs {
+            raw: unsafe { &*args },
+        })
+    })
 }
 
 extern "C" fn destroy_node_resources(user_data: *mut c_void) {
-    get_callbacks(user_data).destroy_node_resources();
+    guard::catch((), || get_callbacks(user_data).destroy_node_resources());
 }
 
 extern "C" fn record_debug_marker(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpRecordDebugMarkerArgs,
 ) {
-    let args = unsafe { &*args };
-    get_callbacks(user_data).record_debug_marker(args);
+    guard::catch((), || {
+        get_callbacks(user_data).record_debug_marker(&RecordDebugMarkerArgs {
+            raw: unsafe { &*args },
+        })
+    });
 }
 
 extern "C" fn set_debug_name(
     user_data: *mut c_void,
     args: *const ffi::RpsRuntimeOpSetDebugNameArgs,
 ) {
-    let args = unsafe { &*args };
-    get_callbacks(user_data).set_debug_name(args);
+    guard::catch((), || {
+        let context = get_context(user_data);
+        let args = SetDebugNameArgs {
+            raw: unsafe { &*args },
+        };
+
+        if let Ok(name) = args.name() {
+            context.debug_names.insert(args.resource(), name);
+        }
+        context.callbacks.set_debug_name(&args);
+    });
+}
+
+/// State of a runtime implemented in Rust, passed as the runtime user context.
+pub(crate) struct RuntimeContext {
+    pub(crate) callbacks: Arc<dyn Callbacks>,
+    pub(crate) debug_names: Arc<DebugNames>,
+}
+
+impl RuntimeContext {
+    pub(crate) fn new(callbacks: Arc<dyn Callbacks>) -> Self {
+        Self {
+            callbacks,
+            debug_names: Arc::default(),
+        }
+    }
 }
 
-fn get_callbacks<'a>(user_data: *mut c_void) -> &'a mut Box<dyn Callbacks> {
-    unsafe { &mut *(user_data as *mut Box<dyn Callbacks>) }
+/// Returns the runtime context passed as the runtime user context.
+///
+/// The user context points to the context owned by the device, which outlives the runtime.
+fn get_context<'a>(user_data: *mut c_void) -> &'a RuntimeContext {
+    unsafe { &*(user_data as *const RuntimeContext) }
+}
+
+/// Returns the callbacks of the runtime context.
+fn get_callbacks<'a>(user_data: *mut c_void) -> &'a dyn Callbacks {
+    get_context(user_data).callbacks.as_ref()
 }
 
 pub(crate) const CALLBACKS: ffi::RpsRuntimeCallbacks = ffi::RpsRuntimeCallbacks {
@@ -862,3 +1703,63 @@ pub(crate) const CALLBACKS: ffi::RpsRuntimeCallbacks = ffi::RpsRuntimeCallbacks
     pfnRecordDebugMarker: Some(record_debug_marker),
     pfnSetDebugName: Some(set_debug_name),
 };
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[test]
+    fn resource_desc_roundtrip() {
+        for desc in [
+            ResourceDesc::buffer(6 << 32 | 256),
+            ResourceDesc::image_2d(Format::D32FloatS8X24Uint, 1920, 1080),
+        ] {
+            let raw = ffi::RpsResourceDesc::from(&desc);
+            assert_eq!(ResourceDesc::try_from(&raw).unwrap(), desc);
+        }
+    }
+
+    #[test]
+    fn create_resource_args() {
+        let name = CString::new("depth").unwrap();
+        let mut resource = RuntimeResource::default();
+
+        let mut raw = ffi::RpsRuntimeOpCreateResourceArgs {
+            resourceIndex: 3,
+            desc: (&ResourceDesc::image_2d(Format::D32Float, 64, 64)).into(),
+            phResource: &mut resource,
+            ..Default::default()
+        };
+        raw.originalClearValue.depthStencil.depth = 1.0;
+        raw.allocPlacement.offset = 4096;
+
+        let mut args = CreateResourceArgs { raw: &raw };
+        assert_eq!(args.resource_id(), 3);
+        assert_eq!(args.desc().unwrap().format, Format::D32Float);
+        assert_eq!(
+            args.clear_value().unwrap(),
+            ClearValue::DepthStencil {
+                depth: 1.0,
+                stencil: 0
+            }
+        );
+        assert_eq!(args.placement().offset, 4096);
+
+        let handle = RuntimeResource {
+            ptr: name.as_ptr() as *mut c_void,
+        };
+        args.set_resource(handle);
+        assert_eq!(resource.ptr, handle.ptr);
+
+        let raw = ffi::RpsRuntimeOpSetDebugNameArgs {
+            hResource: handle,
+            resourceType: ffi::RpsResourceType_RPS_RESOURCE_TYPE_IMAGE_2D,
+            name: name.as_ptr(),
+        };
+        let args = SetDebugNameArgs { raw: &raw };
+        assert_eq!(args.name().unwrap(), "depth");
+        assert_eq!(args.resource_type().unwrap(), ResourceType::Image2D);
+    }
+}
@@ -0,0 +1,467 @@
+//! Runtime backing heaps and resources with host memory.
+
+use std::{
+    alloc::{self, Layout},
+    collections::HashMap,
+    ops::Range,
+    os::raw::c_void,
+    ptr::NonNull,
+    slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use super::{
+    Callbacks, CreateHeapArgs, CreateResourceArgs, DestroyHeapArgs, DestroyResourceArgs,
+    ResourceDesc, RuntimeHeap, RuntimeResource,
+};
+use crate::{diagnostics::Diagnostics, Error};
+
+/// Block of zeroed host memory.
+struct Block {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: The block exclusively owns its memory, which is only accessed through the runtime mutex.
+unsafe impl Send for Block {}
+
+impl Block {
+    fn new(size: u64, alignment: u64) -> Result<Self, Error> {
+        let size = usize::try_from(size).map_err(|_| Error::OutOfMemory)?;
+        let alignment = usize::try_from(alignment.max(1))
+            .ok()
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or(Error::InvalidArguments)?;
+
+        let layout =
+            Layout::from_size_align(size.max(1), alignment).map_err(|_| Error::OutOfMemory)?;
+        let ptr = NonNull::new(unsafe { alloc::alloc_zeroed(layout) }).ok_or(Error::OutOfMemory)?;
+
+        Ok(Self { ptr, layout })
+    }
+
+    fn len(&self) -> usize {
+        self.layout.size()
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+/// Memory of a resource, either placed in a heap or dedicated.
+enum Memory {
+    Placed { heap: usize, offset: usize },
+    Dedicated(Block),
+}
+
+struct Resource {
+    memory: Memory,
+    size: usize,
+}
+
+#[derive(Default)]
+struct State {
+    heaps: HashMap<usize, Block>,
+    resources: HashMap<usize, Resource>,
+}
+
+impl State {
+    /// Returns the start and length of the memory of a resource, within a block owned by the state.
+    fn memory(&self, resource: usize) -> Option<(*mut u8, usize)> {
+        let resource = self.resources.get(&resource)?;
+        let (ptr, len) = match &resource.memory {
+            Memory::Placed { heap, offset } => {
+                let heap = self.heaps.get(heap)?;
+                let ptr = unsafe { heap.ptr.as_ptr().add(*offset) };
+                (ptr, resource.size.min(heap.len() - offset))
+            }
+            Memory::Dedicated(block) => (block.ptr.as_ptr(), resource.size),
+        };
+
+        Some((ptr, len))
+    }
+
+    /// Returns the pairs of resources placed at overlapping ranges of the same heap with overlapping lifetimes.
+    ///
+    /// `lifetime` returns the range of command indices a resource is alive for, `None` if it's never accessed.
+    fn live_overlaps(&self, lifetime: impl Fn(usize) -> Option<Range<u32>>) -> Vec<(usize, usize)> {
+        let mut placed = self
+            .resources
+            .iter()
+            .filter_map(|(handle, resource)| match resource.memory {
+                Memory::Placed { heap, offset } => {
+                    Some((*handle, heap, offset..offset + resource.size))
+                }
+                Memory::Dedicated(_) => None,
+            })
+            .filter_map(|(handle, heap, bytes)| Some((handle, heap, bytes, lifetime(handle)?)))
+            .collect::<Vec<_>>();
+        placed.sort_unstable_by_key(|(handle, ..)| *handle);
+
+        let mut overlaps = Vec::new();
+        for (index, (first, heap, bytes, cmds)) in placed.iter().enumerate() {
+            for (second, other_heap, other_bytes, other_cmds) in &placed[index + 1..] {
+                if heap == other_heap
+                    && bytes.start < other_bytes.end
+                    && other_bytes.start < bytes.end
+                    && cmds.start < other_cmds.end
+                    && other_cmds.start < cmds.end
+                {
+                    overlaps.push((*first, *second));
+                }
+            }
+        }
+
+        overlaps
+    }
+}
+
+/// Runtime callbacks backing heaps and resources with host memory.
+///
+/// Every heap is a block of zeroed host memory, and resources are placed in their heap as RPS instructs, or get a
+/// dedicated block otherwise. Node callbacks can access the contents of the resources with [HostRuntime::with_memory],
+/// which allows to check the data flow through a render graph without a GPU, e.g. with the null runtime of
+/// [crate::DeviceBuilder::build_null].
+///
+/// RPS aliases the memory of resources whose lifetimes don't overlap, so resources may share memory within a heap.
+/// Placements overlapping while both resources are alive are detected by [HostRuntime::overlaps].
+#[derive(Default)]
+pub struct HostRuntime {
+    state: Mutex<State>,
+    last_handle: AtomicUsize,
+}
+
+impl HostRuntime {
+    /// Creates a new host runtime.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Invokes `f` with the memory of a resource.
+    ///
+    /// Returns `None` if the resource wasn't created by this runtime or is already destroyed.
+    ///
+    /// The runtime is locked while `f` runs, so `f` must not access the runtime itself, e.g. by a nested call of
+    /// [HostRuntime::with_memory], which deadlocks. Use [HostRuntime::with_memory2] to access two resources at once.
+    pub fn with_memory<R>(
+        &self,
+        resource: RuntimeResource,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> Option<R> {
+        let state = self.lock();
+        let (ptr, len) = state.memory(resource.ptr as usize)?;
+
+        // SAFETY: The range is within a block owned by the state, which is locked until `f` returns.
+        Some(f(unsafe { slice::from_raw_parts_mut(ptr, len) }))
+    }
+
+    /// Invokes `f` with the memory of a source and a destination resource, e.g. to copy between them.
+    ///
+    /// Returns `None` if either resource wasn't created by this runtime or is already destroyed, or if the memory of
+    /// the resources overlaps, e.g. for the same resource or resources aliased in a heap.
+    ///
+    /// The runtime is locked while `f` runs, with the same restriction as [HostRuntime::with_memory].
+    pub fn with_memory2<R>(
+        &self,
+        src: RuntimeResource,
+        dst: RuntimeResource,
+        f: impl FnOnce(&[u8], &mut [u8]) -> R,
+    ) -> Option<R> {
+        let state = self.lock();
+        let (src_ptr, src_len) = state.memory(src.ptr as usize)?;
+        let (dst_ptr, dst_len) = state.memory(dst.ptr as usize)?;
+
+        let (src_start, dst_start) = (src_ptr as usize, dst_ptr as usize);
+        if src_start < dst_start + dst_len && dst_start < src_start + src_len {
+            return None;
+        }
+
+        // SAFETY: The ranges are disjoint and within blocks owned by the state, which is locked until `f` returns.
+        let (src, dst) = unsafe {
+            (
+                slice::from_raw_parts(src_ptr as *const u8, src_len),
+                slice::from_raw_parts_mut(dst_ptr, dst_len),
+            )
+        };
+        Some(f(src, dst))
+    }
+
+    /// Returns the pairs of live resources placed at overlapping ranges of the same heap while both are alive.
+    ///
+    /// The lifetimes are taken from the diagnostics of the last update, e.g. of [crate::graph::RenderGraph::diagnostics],
+    /// so resources aliased with disjoint lifetimes aren't reported. Resources unknown to `diagnostics` are assumed to
+    /// be alive for the whole frame.
+    pub fn overlaps(&self, diagnostics: &Diagnostics) -> Vec<(RuntimeResource, RuntimeResource)> {
+        let lifetimes = diagnostics
+            .resources
+            .iter()
+            .map(|resource| {
+                (
+                    resource.runtime_resource.ptr as usize,
+                    resource.lifetime.clone(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let handle = |handle: usize| RuntimeResource {
+            ptr: handle as *mut c_void,
+        };
+
+        self.lock()
+            .live_overlaps(|resource| {
+                lifetimes
+                    .get(&resource)
+                    .cloned()
+                    .unwrap_or(Some(0..u32::MAX))
+            })
+            .into_iter()
+            .map(|(first, second)| (handle(first), handle(second)))
+            .collect()
+    }
+
+    /// Returns the number of live heaps.
+    pub fn num_heaps(&self) -> usize {
+        self.lock().heaps.len()
+    }
+
+    /// Returns the number of live resources.
+    pub fn num_resources(&self) -> usize {
+        self.lock().resources.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn next_handle(&self) -> usize {
+        self.last_handle.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Returns the size of the memory backing a resource without explicit memory requirements.
+fn resource_size(desc: &ResourceDesc) -> u64 {
+    if desc.is_buffer() {
+        return desc.width;
+    }
+
+    let mut size = 0;
+    let (mut width, mut height) = (desc.width, u64::from(desc.height));
+    for _ in 0..desc.mip_levels.max(1) {
+        size += width * height;
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+    }
+
+    size * u64::from(desc.depth_or_array_layers.max(1))
+        * u64::from(desc.sample_count.max(1))
+        * u64::from(desc.format.element_bytes())
+}
+
+impl Callbacks for HostRuntime {
+    fn create_heap(&self, args: &mut CreateHeapArgs) -> Result<(), Error> {
+        let block = Block::new(args.size(), u64::from(args.alignment()))?;
+
+        let heap = self.next_handle();
+        self.lock().heaps.insert(heap, block);
+
+        args.set_heap(RuntimeHeap {
+            ptr: heap as *mut c_void,
+        });
+
+        Ok(())
+    }
+
+    fn destroy_heap(&self, args: &DestroyHeapArgs) {
+        let mut state = self.lock();
+        for heap in args.heaps() {
+            state.heaps.remove(&(heap.ptr as usize));
+        }
+    }
+
+    fn create_resource(&self, args: &mut CreateResourceArgs) -> Result<(), Error> {
+        let requirement = args.memory_requirement();
+        let size = match requirement.size {
+            0 => resource_size(&args.desc()?),
+            size => size,
+        };
+        let size = usize::try_from(size).map_err(|_| Error::OutOfMemory)?;
+
+        let resource = self.next_handle();
+        let placement = args.placement();
+        let mut state = self.lock();
+
+        let memory = if placement.heap.ptr.is_null() {
+            Memory::Dedicated(Block::new(size as u64, u64::from(requirement.alignment))?)
+        } else {
+            let heap = placement.heap.ptr as usize;
+            let offset = usize::try_from(placement.offset).map_err(|_| Error::OutOfMemory)?;
+
+            let heap_size = state.heaps.get(&heap).ok_or(Error::InvalidArguments)?.len();
+            if offset.checked_add(size).map_or(true, |end| end > heap_size) {
+                return Err(Error::IndexOutOfBounds);
+            }
+
+            Memory::Placed { heap, offset }
+        };
+
+        state.resources.insert(resource, Resource { memory, size });
+
+        args.set_resource(RuntimeResource {
+            ptr: resource as *mut c_void,
+        });
+
+        Ok(())
+    }
+
+    fn destroy_resource(&self, args: &DestroyResourceArgs) {
+        let mut state = self.lock();
+        for resource in args.resources() {
+            state.resources.remove(&(resource.ptr as usize));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Arc};
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, AccessFlags},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+    use rps_sys as ffi;
+
+    #[test]
+    fn data_flow() {
+        let host = Arc::new(HostRuntime::new());
+        let device = DeviceBuilder::new().build_null(host.clone()).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "fill",
+            vec![ParamDesc::buffer("dst", AccessFlags::UNORDERED_ACCESS)],
+        ));
+        signature.nodes.push(NodeDesc::new(
+            "copy",
+            vec![
+                ParamDesc::buffer("src", AccessFlags::COPY_SRC),
+                ParamDesc::buffer("dst", AccessFlags::COPY_DEST),
+            ],
+        ));
+        signature.nodes.push(NodeDesc::new(
+            "check",
+            vec![ParamDesc::buffer("src", AccessFlags::SHADER_RESOURCE)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("data", 0, &ResourceDesc::buffer(256))?;
+            let copy = builder.declare_resource("copy", 1, &ResourceDesc::buffer(256))?;
+            builder.add_node("fill", &[&runtime::buffer_view(buffer)])?;
+            builder.add_node(
+                "copy",
+                &[&runtime::buffer_view(buffer), &runtime::buffer_view(copy)],
+            )?;
+            builder.add_node("check", &[&runtime::buffer_view(copy)])?;
+            Ok(())
+        });
+
+        let read = RefCell::new(Vec::new());
+
+        let program = graph.main_entry();
+        program
+            .bind_node("fill", |context| {
+                let resource = context.arg_resources(0)?[0];
+                host.with_memory(resource, |memory| memory[..4].copy_from_slice(b"rps!"))
+                    .ok_or(Error::InvalidArguments)
+            })
+            .unwrap();
+        program
+            .bind_node("copy", |context| {
+                let src = context.arg_resources(0)?[0];
+                let dst = context.arg_resources(1)?[0];
+
+                // The memory of a resource can't be borrowed twice.
+                assert!(host.with_memory2(src, src, |_, _| ()).is_none());
+
+                host.with_memory2(src, dst, |src, dst| dst.copy_from_slice(src))
+                    .ok_or(Error::InvalidArguments)
+            })
+            .unwrap();
+        program
+            .bind_node("check", |context| {
+                let resource = context.arg_resources(0)?[0];
+                let data = host
+                    .with_memory(resource, |memory| memory[..4].to_vec())
+                    .ok_or(Error::InvalidArguments)?;
+                read.borrow_mut().push(data);
+                Ok(())
+            })
+            .unwrap();
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+        assert!(host.num_resources() > 0);
+
+        for batch in &graph.batch_layout().unwrap() {
+            graph
+                .record_batch(batch, ffi::RpsRuntimeCommandBuffer::default())
+                .unwrap();
+        }
+
+        assert_eq!(read.into_inner(), [b"rps!".to_vec()]);
+        assert!(host.overlaps(&graph.diagnostics().unwrap()).is_empty());
+
+        drop(graph);
+        drop(device);
+        assert_eq!(host.num_resources(), 0);
+        assert_eq!(host.num_heaps(), 0);
+    }
+
+    #[test]
+    fn live_overlaps() {
+        let mut state = State::default();
+        state.heaps.insert(1, Block::new(1024, 256).unwrap());
+
+        let placed = |offset, size| Resource {
+            memory: Memory::Placed { heap: 1, offset },
+            size,
+        };
+        state.resources.insert(2, placed(0, 512));
+        state.resources.insert(3, placed(256, 512));
+        state.resources.insert(4, placed(512, 512));
+        state.resources.insert(
+            5,
+            Resource {
+                memory: Memory::Dedicated(Block::new(512, 256).unwrap()),
+                size: 512,
+            },
+        );
+
+        // 2 and 4 alias 3 in memory, but only 2 is alive at the same time as 3.
+        let lifetime = |resource| match resource {
+            2 => Some(0..2),
+            3 => Some(1..3),
+            4 => Some(3..4),
+            _ => Some(0..4),
+        };
+        assert_eq!(state.live_overlaps(lifetime), [(2, 3)]);
+
+        // Resources which are never accessed are never alive.
+        let lifetime = |resource| match resource {
+            2 => None,
+            _ => Some(0..4),
+        };
+        assert_eq!(state.live_overlaps(lifetime), [(3, 4)]);
+    }
+}
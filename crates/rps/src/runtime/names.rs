@@ -0,0 +1,160 @@
+//! Registry of the debug names of runtime resources.
+
+use std::{
+    collections::HashMap,
+    os::raw::c_void,
+    sync::{Mutex, MutexGuard},
+};
+
+use super::RuntimeResource;
+
+/// Debug names of the live runtime resources, as set by RPS through [super::Callbacks::set_debug_name].
+///
+/// The registry is maintained by the runtime of a device, see [crate::Device::debug_names], and names are removed when
+/// their resource is destroyed.
+#[derive(Debug, Default)]
+pub struct DebugNames {
+    names: Mutex<HashMap<usize, String>>,
+}
+
+impl DebugNames {
+    /// Returns the debug name of a resource.
+    pub fn get(&self, resource: RuntimeResource) -> Option<String> {
+        self.lock().get(&(resource.ptr as usize)).cloned()
+    }
+
+    /// Returns the debug name of a resource, or the formatted handle if it has no name.
+    pub fn describe(&self, resource: RuntimeResource) -> String {
+        self.get(resource)
+            .unwrap_or_else(|| format!("{:p}", resource.ptr))
+    }
+
+    /// Returns the number of named resources.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no resource is named.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the named resources and their names.
+    pub fn entries(&self) -> Vec<(RuntimeResource, String)> {
+        self.lock()
+            .iter()
+            .map(|(resource, name)| {
+                let resource = RuntimeResource {
+                    ptr: *resource as *mut c_void,
+                };
+                (resource, name.clone())
+            })
+            .collect()
+    }
+
+    pub(crate) fn insert(&self, resource: RuntimeResource, name: &str) {
+        if resource.ptr.is_null() {
+            return;
+        }
+
+        self.lock().insert(resource.ptr as usize, name.to_string());
+    }
+
+    pub(crate) fn remove(&self, resources: &[RuntimeResource]) {
+        let mut names = self.lock();
+        for resource in resources {
+            names.remove(&(resource.ptr as usize));
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<usize, String>> {
+        self.names.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ptr, sync::Arc};
+
+    use super::*;
+    use crate::{
+        graph::{DiagnosticFlags, RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, AccessFlags, RecordingCallbacks, ResourceDesc, RuntimeOp},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    #[test]
+    fn register_names() {
+        let names = DebugNames::default();
+        let resource = |handle: usize| RuntimeResource {
+            ptr: handle as *mut c_void,
+        };
+
+        names.insert(resource(1), "GBufferNormal");
+        names.insert(resource(2), "Depth");
+        names.insert(resource(0), "Null");
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get(resource(1)).as_deref(), Some("GBufferNormal"));
+
+        names.insert(resource(1), "GBufferAlbedo");
+        assert_eq!(names.describe(resource(1)), "GBufferAlbedo");
+
+        names.remove(&[resource(1), resource(2)]);
+        assert!(names.is_empty());
+        assert_eq!(
+            names.describe(resource(1)),
+            format!("{:p}", 1 as *mut c_void)
+        );
+        assert!(names
+            .get(RuntimeResource {
+                ptr: ptr::null_mut()
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn debug_names() {
+        let callbacks = Arc::new(RecordingCallbacks::new());
+        let device = DeviceBuilder::new().build_null(callbacks.clone()).unwrap();
+        let names = device.debug_names().unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "fill",
+            vec![ParamDesc::buffer("dst", AccessFlags::UNORDERED_ACCESS)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("GBufferNormal", 0, &ResourceDesc::buffer(64))?;
+            builder.add_node("fill", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+        graph
+            .update(UpdateInfo::new(0).diagnostic_flags(DiagnosticFlags::RUNTIME_DEBUG_NAMES))
+            .unwrap();
+
+        // The name set by RPS is registered for its resource.
+        let resource = callbacks
+            .ops()
+            .into_iter()
+            .find_map(|op| match op {
+                RuntimeOp::DebugName { resource, name } if name == "GBufferNormal" => {
+                    Some(resource)
+                }
+                _ => None,
+            })
+            .expect("no debug name set");
+        let resource = RuntimeResource {
+            ptr: resource as *mut c_void,
+        };
+        assert_eq!(names.get(resource).as_deref(), Some("GBufferNormal"));
+
+        drop(graph);
+        drop(device);
+        assert!(names.is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+//! Runtime callbacks recording the runtime operations.
+
+use std::{
+    os::raw::c_void,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use super::{
+    AccessFlags, Callbacks, CreateHeapArgs, CreateResourceArgs, DebugMarkerMode, DestroyHeapArgs,
+    DestroyResourceArgs, RecordDebugMarkerArgs, ResourceDesc, ResourceId, RuntimeHeap,
+    RuntimeResource, SetDebugNameArgs,
+};
+use crate::{
+    backend::{RuntimeBackend, Transition},
+    Error,
+};
+use rps_sys as ffi;
+
+/// Runtime operation recorded by [RecordingCallbacks].
+///
+/// Heaps and resources are identified by the handles assigned by the recording callbacks, starting from 1 in order
+/// of creation.
+///
+/// Resource transitions aren't runtime callbacks, they are recorded when the recording callbacks are used as the
+/// backend of [crate::graph::RenderGraph::record_batch_with].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeOp {
+    /// A heap was created.
+    CreateHeap {
+        /// Handle of the heap.
+        heap: usize,
+        /// Size of the heap in bytes.
+        size: u64,
+        /// Alignment of the heap in bytes.
+        alignment: u32,
+        /// Index of the memory type of the heap.
+        memory_type_index: u32,
+    },
+    /// A heap was destroyed.
+    DestroyHeap {
+        /// Handle of the heap.
+        heap: usize,
+    },
+    /// A resource was created.
+    CreateResource {
+        /// Handle of the resource.
+        resource: usize,
+        /// Identifier of the resource in the render graph.
+        id: ResourceId,
+        /// Description of the resource.
+        desc: ResourceDesc,
+        /// Handle of the heap the resource is placed in, if any.
+        heap: Option<usize>,
+        /// Offset of the resource in the heap in bytes.
+        offset: u64,
+    },
+    /// A resource was destroyed.
+    DestroyResource {
+        /// Handle of the resource.
+        resource: usize,
+    },
+    /// A debug marker was recorded.
+    DebugMarker {
+        /// Kind of the marker.
+        mode: DebugMarkerMode,
+        /// Text of the marker.
+        text: String,
+    },
+    /// A resource was transitioned between two accesses.
+    Transition {
+        /// Identifier of the resource in the render graph.
+        id: ResourceId,
+        /// Access of the resource before the transition.
+        prev_access: AccessFlags,
+        /// Access of the resource after the transition.
+        next_access: AccessFlags,
+    },
+    /// A debug name was set on a resource.
+    DebugName {
+        /// Handle of the resource.
+        resource: usize,
+        /// Debug name of the resource.
+        name: String,
+    },
+}
+
+/// Runtime callbacks recording every runtime operation in order.
+///
+/// Combined with the null runtime of [crate::DeviceBuilder::build_null], it allows to check exactly which heaps and
+/// resources a render graph creates. Keep a clone of the `Arc` passed to the device to read the recorded operations.
+#[derive(Debug, Default)]
+pub struct RecordingCallbacks {
+    ops: Mutex<Vec<RuntimeOp>>,
+    last_handle: AtomicUsize,
+}
+
+impl RecordingCallbacks {
+    /// Creates new recording callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the operations recorded so far.
+    pub fn ops(&self) -> Vec<RuntimeOp> {
+        self.lock().clone()
+    }
+
+    /// Returns the operations recorded so far and clears the log.
+    pub fn take(&self) -> Vec<RuntimeOp> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<RuntimeOp>> {
+        self.ops.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn push(&self, op: RuntimeOp) {
+        self.lock().push(op);
+    }
+
+    fn next_handle(&self) -> usize {
+        self.last_handle.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl Callbacks for RecordingCallbacks {
+    fn create_heap(&self, args: &mut CreateHeapArgs) -> Result<(), Error> {
+        let heap = self.next_handle();
+        args.set_heap(RuntimeHeap {
+            ptr: heap as *mut c_void,
+        });
+
+        self.push(RuntimeOp::CreateHeap {
+            heap,
+            size: args.size(),
+            alignment: args.alignment(),
+            memory_type_index: args.memory_type_index(),
+        });
+
+        Ok(())
+    }
+
+    fn destroy_heap(&self, args: &DestroyHeapArgs) {
+        for heap in args.heaps() {
+            self.push(RuntimeOp::DestroyHeap {
+                heap: heap.ptr as usize,
+            });
+        }
+    }
+
+    fn create_resource(&self, args: &mut CreateResourceArgs) -> Result<(), Error> {
+        let desc = args.desc()?;
+        let placement = args.placement();
+
+        let resource = self.next_handle();
+        args.set_resource(RuntimeResource {
+            ptr: resource as *mut c_void,
+        });
+
+        self.push(RuntimeOp::CreateResource {
+            resource,
+            id: args.resource_id(),
+            desc,
+            heap: (!placement.heap.ptr.is_null()).then_some(placement.heap.ptr as usize),
+            offset: placement.offset,
+        });
+
+        Ok(())
+    }
+
+    fn destroy_resource(&self, args: &DestroyResourceArgs) {
+        for resource in args.resources() {
+            self.push(RuntimeOp::DestroyResource {
+                resource: resource.ptr as usize,
+            });
+        }
+    }
+
+    fn record_debug_marker(&self, args: &RecordDebugMarkerArgs) {
+        if let Ok(mode) = args.mode() {
+            self.push(RuntimeOp::DebugMarker {
+                mode,
+                text: args.text().unwrap_or_default().to_string(),
+            });
+        }
+    }
+
+    fn set_debug_name(&self, args: &SetDebugNameArgs) {
+        self.push(RuntimeOp::DebugName {
+            resource: args.resource().ptr as usize,
+            name: args.name().unwrap_or_default().to_string(),
+        });
+    }
+}
+
+/// Records the transitions of [crate::graph::RenderGraph::record_batch_with], e.g. with `&mut &*callbacks`, and the
+/// node commands with the bound node callbacks.
+impl<'a> RuntimeBackend for &'a RecordingCallbacks {
+    fn record_transitions(
+        &mut self,
+        _cmd_buffer: ffi::RpsRuntimeCommandBuffer,
+        transitions: &[Transition],
+    ) -> Result<(), Error> {
+        let mut ops = self.lock();
+        ops.extend(transitions.iter().map(|transition| RuntimeOp::Transition {
+            id: transition.resource_index,
+            prev_access: transition.prev_access,
+            next_access: transition.next_access,
+        }));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        graph::{RenderGraphCreateInfo, UpdateInfo},
+        runtime::{self, AccessFlags, Format},
+        signature::{EntrySignature, NodeDesc, ParamDesc},
+        DeviceBuilder,
+    };
+
+    #[test]
+    fn record_resources() {
+        let callbacks = Arc::new(RecordingCallbacks::new());
+        let device = DeviceBuilder::new().build_null(callbacks.clone()).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "clear",
+            vec![ParamDesc::image("target", AccessFlags::RENDER_TARGET)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+
+        let desc = ResourceDesc::image_2d(Format::R16G16B16A16Float, 256, 256);
+        graph.set_build_callback(|builder| {
+            let color = builder.declare_resource("color", 0, &desc)?;
+            builder.add_node("clear", &[&runtime::image_view(color)])?;
+            Ok(())
+        });
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+
+        let created = callbacks
+            .ops()
+            .into_iter()
+            .filter_map(|op| match op {
+                RuntimeOp::CreateResource { desc, .. } => Some(desc),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(created, [desc]);
+
+        drop(graph);
+        drop(device);
+
+        let ops = callbacks.take();
+        for op in &ops {
+            if let RuntimeOp::CreateResource { resource, .. } = op {
+                assert!(ops.contains(&RuntimeOp::DestroyResource {
+                    resource: *resource
+                }));
+            }
+        }
+        assert!(callbacks.ops().is_empty());
+    }
+
+    #[test]
+    fn record_transitions() {
+        let callbacks = Arc::new(RecordingCallbacks::new());
+        let device = DeviceBuilder::new().build_null(callbacks.clone()).unwrap();
+
+        let mut signature = EntrySignature::new("main");
+        signature.nodes.push(NodeDesc::new(
+            "fill",
+            vec![ParamDesc::buffer("dst", AccessFlags::UNORDERED_ACCESS)],
+        ));
+        signature.nodes.push(NodeDesc::new(
+            "read",
+            vec![ParamDesc::buffer("src", AccessFlags::SHADER_RESOURCE)],
+        ));
+
+        let mut graph = device
+            .create_render_graph(RenderGraphCreateInfo::new().signature(signature))
+            .unwrap();
+        graph.set_build_callback(|builder| {
+            let buffer = builder.declare_resource("data", 0, &ResourceDesc::buffer(256))?;
+            builder.add_node("fill", &[&runtime::buffer_view(buffer)])?;
+            builder.add_node("read", &[&runtime::buffer_view(buffer)])?;
+            Ok(())
+        });
+        for name in ["fill", "read"] {
+            graph.main_entry().bind_node(name, |_| Ok(())).unwrap();
+        }
+
+        graph.update(&UpdateInfo::new(0)).unwrap();
+        callbacks.take();
+
+        for batch in &graph.batch_layout().unwrap() {
+            graph
+                .record_batch_with(
+                    &mut &*callbacks,
+                    batch,
+                    ffi::RpsRuntimeCommandBuffer::default(),
+                )
+                .unwrap();
+        }
+
+        // The buffer written by the first node is transitioned for the second one to read it.
+        assert!(callbacks.take().iter().any(|op| matches!(
+            op,
+            RuntimeOp::Transition { id: 0, prev_access, next_access }
+                if prev_access.contains(AccessFlags::UNORDERED_ACCESS)
+                    && next_access.contains(AccessFlags::SHADER_RESOURCE)
+        )));
+    }
+}
@@ -0,0 +1,363 @@
+//! Runtime callbacks forwarding debug markers to `tracing`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ptr,
+    sync::{
+        atomic::{self, AtomicPtr},
+        Arc, Mutex,
+    },
+};
+
+use tracing::{
+    callsite::{Callsite, Identifier},
+    field::{FieldSet, Value},
+    level_filters::LevelFilter,
+    metadata::Kind,
+    span::Id,
+    subscriber::Interest,
+    Level, Metadata, Span,
+};
+
+use super::{
+    Callbacks, CreateHeapArgs, CreateNodeResourcesArgs, CreateResourceArgs, DebugMarkerMode,
+    DestroyHeapArgs, DestroyResourceArgs, RecordDebugMarkerArgs, SetDebugNameArgs,
+};
+use crate::{phase::Phases, Error};
+use rps_sys as ffi;
+
+/// Runtime callbacks turning debug markers into `tracing` spans and events.
+///
+/// Debug marker regions become nested spans and labels become events, both under the `rps` target at the `INFO` level.
+/// Spans are named after the marker text, usually the node name, which is also recorded as the `name` field. All other
+/// callbacks are forwarded to the inner callbacks, if any.
+///
+/// `tracing` keeps span metadata for the lifetime of the program, so the metadata of every distinct marker text is
+/// leaked once. Avoid per-frame text like frame numbers in marker regions.
+///
+/// Regions are tracked per command buffer, so the spans of command buffers recorded concurrently, or interleaved on one
+/// thread, nest independently. The spans aren't entered, a region is closed by its end marker.
+#[derive(Default)]
+pub struct TracingCallbacks {
+    inner: Option<Arc<dyn Callbacks>>,
+    /// Spans of the open regions per command buffer, innermost last.
+    spans: Mutex<HashMap<usize, Vec<Span>>>,
+}
+
+impl TracingCallbacks {
+    /// Creates tracing callbacks without inner callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates tracing callbacks forwarding all other callbacks to `inner`.
+    pub fn with_inner(inner: Arc<dyn Callbacks>) -> Self {
+        Self {
+            inner: Some(inner),
+            ..Self::default()
+        }
+    }
+}
+
+impl Callbacks for TracingCallbacks {
+    fn build_render_graph_phases(
+        &self,
+        render_graph: ffi::RpsRenderGraph,
+        phases: &mut Phases,
+    ) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.build_render_graph_phases(render_graph, phases),
+            None => Ok(()),
+        }
+    }
+
+    fn destroy_runtime(&self) {
+        if let Some(inner) = &self.inner {
+            inner.destroy_runtime();
+        }
+    }
+
+    fn create_heap(&self, args: &mut CreateHeapArgs) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.create_heap(args),
+            None => Ok(()),
+        }
+    }
+
+    fn destroy_heap(&self, args: &DestroyHeapArgs) {
+        if let Some(inner) = &self.inner {
+            inner.destroy_heap(args);
+        }
+    }
+
+    fn create_resource(&self, args: &mut CreateResourceArgs) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.create_resource(args),
+            None => Ok(()),
+        }
+    }
+
+    fn destroy_resource(&self, args: &DestroyResourceArgs) {
+        if let Some(inner) = &self.inner {
+            inner.destroy_resource(args);
+        }
+    }
+
+    fn create_node_resources(&self, args: &CreateNodeResourcesArgs) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.create_node_resources(args),
+            None => Ok(()),
+        }
+    }
+
+    fn destroy_node_resources(&self) {
+        if let Some(inner) = &self.inner {
+            inner.destroy_node_resources();
+        }
+    }
+
+    fn record_debug_marker(&self, args: &RecordDebugMarkerArgs) {
+        let text = args.text().unwrap_or_default();
+        let cmd_buffer = args.command_buffer().ptr as usize;
+        let mut spans = self.spans.lock().unwrap_or_else(|err| err.into_inner());
+
+        match args.mode() {
+            Ok(DebugMarkerMode::Begin) => {
+                let stack = spans.entry(cmd_buffer).or_default();
+                let parent = stack.last().unwrap_or(&Span::current()).id();
+                stack.push(marker_span(parent, text));
+            }
+            Ok(DebugMarkerMode::Label) => {
+                let parent = spans
+                    .get(&cmd_buffer)
+                    .and_then(|stack| stack.last())
+                    .unwrap_or(&Span::current())
+                    .id();
+                tracing::info!(target: "rps", parent: parent, name = text, "label");
+            }
+            Ok(DebugMarkerMode::End) => {
+                // Closes the span when dropped.
+                if let Some(stack) = spans.get_mut(&cmd_buffer) {
+                    stack.pop();
+                    if stack.is_empty() {
+                        spans.remove(&cmd_buffer);
+                    }
+                }
+            }
+            Err(_) => {}
+        }
+        drop(spans);
+
+        if let Some(inner) = &self.inner {
+            inner.record_debug_marker(args);
+        }
+    }
+
+    fn set_debug_name(&self, args: &SetDebugNameArgs) {
+        if let Some(inner) = &self.inner {
+            inner.set_debug_name(args);
+        }
+    }
+}
+
+/// Callsite of the spans of one marker text, so the span name can be the text rather than a static name.
+struct MarkerCallsite {
+    /// Leaked metadata, set before the callsite is registered.
+    metadata: AtomicPtr<Metadata<'static>>,
+}
+
+impl Callsite for MarkerCallsite {
+    fn set_interest(&self, _interest: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        // SAFETY: The metadata is set when the callsite is created, before it is shared.
+        unsafe { &*self.metadata.load(atomic::Ordering::Acquire) }
+    }
+}
+
+/// Callsites of the marker spans by text.
+static CALLSITES: Mutex<BTreeMap<String, &'static MarkerCallsite>> = Mutex::new(BTreeMap::new());
+
+/// Returns the span metadata for a marker text, registering a callsite for it on first use.
+fn marker_metadata(text: &str) -> &'static Metadata<'static> {
+    let mut callsites = CALLSITES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(&callsite) = callsites.get(text) {
+        return callsite.metadata();
+    }
+
+    let callsite: &'static MarkerCallsite = Box::leak(Box::new(MarkerCallsite {
+        metadata: AtomicPtr::new(ptr::null_mut()),
+    }));
+    let metadata: &'static mut Metadata<'static> = Box::leak(Box::new(Metadata::new(
+        Box::leak(text.to_owned().into_boxed_str()),
+        "rps",
+        Level::INFO,
+        Some(file!()),
+        Some(line!()),
+        Some(module_path!()),
+        FieldSet::new(&["name"], Identifier(callsite)),
+        Kind::SPAN,
+    )));
+    callsite.metadata.store(metadata, atomic::Ordering::Release);
+
+    tracing::callsite::register(callsite);
+    callsites.insert(text.to_owned(), callsite);
+    callsite.metadata()
+}
+
+/// Creates the span of a marker region named after its text, like `info_span!` would with a dynamic name.
+fn marker_span(parent: Option<Id>, text: &str) -> Span {
+    let metadata = marker_metadata(text);
+    let enabled = *metadata.level() <= LevelFilter::current()
+        && tracing::dispatcher::get_default(|dispatch| dispatch.enabled(metadata));
+    let field = match metadata.fields().field("name") {
+        Some(field) if enabled => field,
+        _ => return Span::none(),
+    };
+
+    Span::child_of(
+        parent,
+        metadata,
+        &metadata
+            .fields()
+            .value_set(&[(&field, Some(&text as &dyn Value))]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::CString,
+        fmt,
+        os::raw::c_void,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+
+    /// Subscriber collecting the name of every span along with its parent.
+    #[derive(Default)]
+    struct Collector {
+        next_id: AtomicU64,
+        spans: Mutex<Vec<(u64, String, Option<u64>)>>,
+        closed: Mutex<Vec<u64>>,
+        labels: Mutex<Vec<(String, Option<u64>)>>,
+    }
+
+    struct NameVisitor(String);
+
+    impl Visit for NameVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "name" {
+                self.0 = value.to_string();
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+            let mut name = NameVisitor(String::new());
+            span.record(&mut name);
+            assert_eq!(name.0, span.metadata().name());
+            let parent = span.parent().map(Id::into_u64);
+            self.spans.lock().unwrap().push((id, name.0, parent));
+            Id::from_u64(id)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut name = NameVisitor(String::new());
+            event.record(&mut name);
+            let parent = event.parent().map(Id::into_u64);
+            self.labels.lock().unwrap().push((name.0, parent));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+
+        fn try_close(&self, id: Id) -> bool {
+            self.closed.lock().unwrap().push(id.into_u64());
+            true
+        }
+    }
+
+    fn marker(
+        callbacks: &TracingCallbacks,
+        cmd_buffer: usize,
+        mode: ffi::RpsRuntimeDebugMarkerMode,
+        text: &str,
+    ) {
+        let text = CString::new(text).unwrap();
+        let raw = ffi::RpsRuntimeOpRecordDebugMarkerArgs {
+            hCommandBuffer: ffi::RpsRuntimeCommandBuffer {
+                ptr: cmd_buffer as *mut c_void,
+            },
+            mode,
+            text: text.as_ptr(),
+            ..Default::default()
+        };
+
+        callbacks.record_debug_marker(&RecordDebugMarkerArgs { raw: &raw });
+    }
+
+    #[test]
+    fn nested_markers() {
+        use ffi::{
+            RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_BEGIN as BEGIN,
+            RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_END as END,
+            RpsRuntimeDebugMarkerMode_RPS_RUNTIME_DEBUG_MARKER_LABEL as LABEL,
+        };
+
+        let dispatch = tracing::Dispatch::new(Collector::default());
+        let callbacks = TracingCallbacks::new();
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            // Two command buffers recorded interleaved on the same thread.
+            marker(&callbacks, 1, BEGIN, "frame");
+            marker(&callbacks, 2, BEGIN, "async");
+            marker(&callbacks, 1, BEGIN, "gbuffer");
+            marker(&callbacks, 1, LABEL, "draw");
+            marker(&callbacks, 2, END, "");
+            marker(&callbacks, 1, END, "");
+            marker(&callbacks, 1, END, "");
+        });
+
+        let collector = dispatch.downcast_ref::<Collector>().unwrap();
+        let spans = collector.spans.lock().unwrap().clone();
+        let span = |name: &str| spans.iter().find(|span| span.1 == name).unwrap().clone();
+        let (frame, async_, gbuffer) = (span("frame"), span("async"), span("gbuffer"));
+        assert_eq!(spans.len(), 3);
+        assert_eq!(frame.2, None);
+        assert_eq!(async_.2, None);
+        assert_eq!(gbuffer.2, Some(frame.0));
+
+        assert_eq!(
+            *collector.labels.lock().unwrap(),
+            [("draw".to_string(), Some(gbuffer.0))]
+        );
+
+        // Every region is closed by its end marker, innermost first.
+        assert_eq!(
+            *collector.closed.lock().unwrap(),
+            [async_.0, gbuffer.0, frame.0]
+        );
+        assert!(callbacks.spans.lock().unwrap().is_empty());
+    }
+}
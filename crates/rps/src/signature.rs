@@ -0,0 +1,396 @@
+//! Render graph entry and node signatures.
+
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::c_char,
+    ptr, slice,
+};
+
+use bitflags::bitflags;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    runtime::{AccessFlags, BufferView, ImageView, Semantic, ShaderStage},
+    Error,
+};
+use rps_sys as ffi;
+
+bitflags! {
+    /// Bitflags for properties of a render graph parameter.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+    pub struct ParamFlags: u32 {
+        /// Parameter is an output parameter.
+        const OUT = ffi::RpsParameterFlagBits_RPS_PARAMETER_FLAG_OUT_BIT;
+        /// Parameter is optional.
+        const OPTIONAL = ffi::RpsParameterFlagBits_RPS_PARAMETER_FLAG_OPTIONAL_BIT;
+        /// Parameter is a resource view.
+        const RESOURCE = ffi::RpsParameterFlagBits_RPS_PARAMETER_FLAG_RESOURCE_BIT;
+    }
+}
+
+bitflags! {
+    /// Bitflags for properties of a node declaration.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+    pub struct NodeFlags: u32 {
+        /// Node requires a queue with graphics capabilities.
+        const GRAPHICS = ffi::RpsNodeDeclFlagBits_RPS_NODE_DECL_GRAPHICS_BIT;
+        /// Node requires a queue with compute capabilities.
+        const COMPUTE = ffi::RpsNodeDeclFlagBits_RPS_NODE_DECL_COMPUTE_BIT;
+        /// Node requires a queue with copy capabilities.
+        const COPY = ffi::RpsNodeDeclFlagBits_RPS_NODE_DECL_COPY_BIT;
+        /// Node prefers to be executed as a render pass.
+        const PREFER_RENDER_PASS = ffi::RpsNodeDeclFlagBits_RPS_NODE_DECL_PREFER_RENDER_PASS;
+        /// Node prefers to be executed on a secondary queue.
+        const PREFER_ASYNC = ffi::RpsNodeDeclFlagBits_RPS_NODE_DECL_PREFER_ASYNC;
+    }
+}
+
+/// Parameter of a render graph entry or a node.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParamDesc {
+    /// Name of the parameter.
+    pub name: String,
+    /// Size of the parameter type in bytes.
+    pub type_size: u16,
+    /// Identifier of the parameter type.
+    pub type_id: u16,
+    /// Number of array elements, 0 if the parameter is not an array.
+    pub array_size: u32,
+    /// Required access of a resource parameter.
+    pub access: AccessFlags,
+    /// Shader stages a resource parameter is accessed from.
+    pub stages: ShaderStage,
+    /// Usage semantic of the parameter.
+    pub semantic: Semantic,
+    /// Index of the semantic, e.g. the render target slot.
+    pub semantic_index: u32,
+    /// Parameter properties.
+    pub flags: ParamFlags,
+}
+
+impl ParamDesc {
+    /// Creates a description of a plain data parameter of type `T`.
+    pub fn new<T: Copy>(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            type_size: mem::size_of::<T>() as u16,
+            type_id: ffi::RpsBuiltInTypeIds_RPS_TYPE_OPAQUE as u16,
+            array_size: 0,
+            access: AccessFlags::empty(),
+            stages: ShaderStage::empty(),
+            semantic: Semantic::Unspecified,
+            semantic_index: 0,
+            flags: ParamFlags::empty(),
+        }
+    }
+
+    /// Creates a description of an image view parameter with the given access.
+    pub fn image(name: &str, access: AccessFlags) -> Self {
+        Self {
+            type_id: ffi::RpsBuiltInTypeIds_RPS_TYPE_IMAGE_VIEW as u16,
+            access,
+            flags: ParamFlags::RESOURCE,
+            ..Self::new::<ImageView>(name)
+        }
+    }
+
+    /// Creates a description of a buffer view parameter with the given access.
+    pub fn buffer(name: &str, access: AccessFlags) -> Self {
+        Self {
+            type_id: ffi::RpsBuiltInTypeIds_RPS_TYPE_BUFFER_VIEW as u16,
+            access,
+            flags: ParamFlags::RESOURCE,
+            ..Self::new::<BufferView>(name)
+        }
+    }
+
+    /// Returns whether the parameter is a resource view.
+    #[inline]
+    pub fn is_resource(&self) -> bool {
+        self.flags.contains(ParamFlags::RESOURCE)
+    }
+
+    /// Returns the size of a value of the parameter in bytes, covering all elements of an array parameter.
+    #[inline]
+    pub fn data_size(&self) -> usize {
+        usize::from(self.type_size) * self.array_size.max(1) as usize
+    }
+
+    fn attr(&self) -> ffi::RpsParamAttr {
+        ffi::RpsParamAttr {
+            access: ffi::RpsAccessAttr {
+                accessFlags: self.access.bits() as ffi::RpsAccessFlags,
+                accessStages: self.stages.bits(),
+            },
+            semantic: ffi::RpsSemanticAttr {
+                semantic: self.semantic as ffi::RpsSemantic,
+                semanticIndex: self.semantic_index,
+            },
+        }
+    }
+}
+
+impl TryFrom<&ffi::RpsParameterDesc> for ParamDesc {
+    type Error = Error;
+
+    fn try_from(value: &ffi::RpsParameterDesc) -> Result<Self, Self::Error> {
+        let attr = unsafe { value.attr.as_ref() }.copied().unwrap_or_default();
+
+        Ok(Self {
+            name: string(value.name)?,
+            type_size: value.typeInfo.size,
+            type_id: value.typeInfo.id,
+            array_size: value.arraySize,
+            access: AccessFlags::from_bits_truncate(attr.access.accessFlags as i32),
+            stages: ShaderStage::from_bits_truncate(attr.access.accessStages),
+            semantic: Semantic::try_from(attr.semantic.semantic)?,
+            semantic_index: attr.semantic.semanticIndex,
+            flags: ParamFlags::from_bits_truncate(value.flags),
+        })
+    }
+}
+
+/// Node declaration.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeDesc {
+    /// Name of the node.
+    pub name: String,
+    /// Parameters of the node.
+    pub params: Vec<ParamDesc>,
+    /// Node properties.
+    pub flags: NodeFlags,
+}
+
+impl NodeDesc {
+    /// Creates a node declaration running on a graphics queue.
+    pub fn new(name: &str, params: Vec<ParamDesc>) -> Self {
+        Self {
+            name: name.to_string(),
+            params,
+            flags: NodeFlags::GRAPHICS,
+        }
+    }
+}
+
+impl TryFrom<&ffi::RpsNodeDesc> for NodeDesc {
+    type Error = Error;
+
+    fn try_from(value: &ffi::RpsNodeDesc) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: string(value.name)?,
+            params: params(value.pParamDescs, value.numParams)?,
+            flags: NodeFlags::from_bits_truncate(value.flags),
+        })
+    }
+}
+
+/// Signature of a render graph main entry.
+///
+/// Describes the parameters of the entry and the nodes that can be added to the render graph.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EntrySignature {
+    /// Name of the entry.
+    pub name: String,
+    /// Parameters of the entry.
+    pub params: Vec<ParamDesc>,
+    /// Declarations of the nodes.
+    pub nodes: Vec<NodeDesc>,
+    /// Maximum number of external resources passed as arguments.
+    pub max_external_resources: u32,
+}
+
+impl EntrySignature {
+    /// Creates an empty signature.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the index of an entry parameter by name.
+    pub fn param_index(&self, name: &str) -> Option<usize> {
+        self.params.iter().position(|param| param.name == name)
+    }
+
+    /// Returns a node declaration by name.
+    pub fn node(&self, name: &str) -> Option<&NodeDesc> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// Returns the index of a node declaration by name.
+    pub fn node_index(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    pub(crate) fn to_raw(&self) -> Result<RawSignature, Error> {
+        let all_params = || {
+            self.params
+                .iter()
+                .chain(self.nodes.iter().flat_map(|node| node.params.iter()))
+        };
+
+        let mut names = Vec::new();
+        let attrs = all_params().map(ParamDesc::attr).collect::<Vec<_>>();
+
+        let mut params = Vec::with_capacity(attrs.len());
+        for (param, attr) in all_params().zip(attrs.iter()) {
+            params.push(ffi::RpsParameterDesc {
+                typeInfo: ffi::RpsTypeInfo {
+                    size: param.type_size,
+                    id: param.type_id,
+                },
+                arraySize: param.array_size,
+                attr,
+                name: intern(&mut names, &param.name)?,
+                flags: param.flags.bits(),
+            });
+        }
+
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        let mut offset = self.params.len();
+        for node in &self.nodes {
+            nodes.push(ffi::RpsNodeDesc {
+                flags: node.flags.bits(),
+                numParams: node.params.len() as u32,
+                pParamDescs: params[offset..].as_ptr(),
+                name: intern(&mut names, &node.name)?,
+            });
+            offset += node.params.len();
+        }
+
+        let desc = ffi::RpsRenderGraphSignatureDesc {
+            numParams: self.params.len() as u32,
+            numNodeDescs: nodes.len() as u32,
+            maxExternalResources: self.max_external_resources,
+            pParamDescs: params.as_ptr(),
+            pNodeDescs: nodes.as_ptr(),
+            name: intern(&mut names, &self.name)?,
+        };
+
+        Ok(RawSignature {
+            _names: names,
+            _attrs: attrs,
+            _params: params,
+            _nodes: nodes,
+            desc,
+        })
+    }
+}
+
+impl TryFrom<&ffi::RpsRenderGraphSignatureDesc> for EntrySignature {
+    type Error = Error;
+
+    fn try_from(value: &ffi::RpsRenderGraphSignatureDesc) -> Result<Self, Self::Error> {
+        let nodes = match value.numNodeDescs {
+            0 => &[],
+            num => unsafe { slice::from_raw_parts(value.pNodeDescs, num as usize) },
+        };
+
+        Ok(Self {
+            name: string(value.name)?,
+            params: params(value.pParamDescs, value.numParams)?,
+            nodes: nodes
+                .iter()
+                .map(NodeDesc::try_from)
+                .collect::<Result<_, _>>()?,
+            max_external_resources: value.maxExternalResources,
+        })
+    }
+}
+
+fn params(ptr: *const ffi::RpsParameterDesc, num: u32) -> Result<Vec<ParamDesc>, Error> {
+    if num == 0 {
+        return Ok(Vec::new());
+    }
+
+    unsafe { slice::from_raw_parts(ptr, num as usize) }
+        .iter()
+        .map(ParamDesc::try_from)
+        .collect()
+}
+
+fn string(ptr: *const c_char) -> Result<String, Error> {
+    if ptr.is_null() {
+        return Ok(String::new());
+    }
+
+    let str = unsafe { CStr::from_ptr(ptr) };
+    str.to_str()
+        .map(str::to_string)
+        .map_err(|_| Error::InvalidData)
+}
+
+fn intern(names: &mut Vec<CString>, name: &str) -> Result<*const c_char, Error> {
+    if name.is_empty() {
+        return Ok(ptr::null());
+    }
+
+    let name = CString::new(name).map_err(|_| Error::InvalidArguments)?;
+    let ptr = name.as_ptr();
+    names.push(name);
+
+    Ok(ptr)
+}
+
+/// `ffi::RpsRenderGraphSignatureDesc` along with the storage it points to.
+pub(crate) struct RawSignature {
+    _names: Vec<CString>,
+    _attrs: Vec<ffi::RpsParamAttr>,
+    _params: Vec<ffi::RpsParameterDesc>,
+    _nodes: Vec<ffi::RpsNodeDesc>,
+    desc: ffi::RpsRenderGraphSignatureDesc,
+}
+
+impl RawSignature {
+    pub(crate) fn desc(&self) -> &ffi::RpsRenderGraphSignatureDesc {
+        &self.desc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflect_raw_signature() {
+        let mut signature = EntrySignature::new("main");
+        signature.params.push(ParamDesc::new::<u32>("count"));
+        signature.nodes.push(NodeDesc::new(
+            "draw",
+            vec![
+                ParamDesc {
+                    semantic: Semantic::RenderTarget,
+                    semantic_index: 1,
+                    ..ParamDesc::image("target", AccessFlags::RENDER_TARGET)
+                },
+                ParamDesc::buffer("vertices", AccessFlags::VERTEX_BUFFER),
+            ],
+        ));
+        signature.max_external_resources = 2;
+
+        let raw = signature.to_raw().unwrap();
+        let reflected = EntrySignature::try_from(raw.desc()).unwrap();
+
+        assert_eq!(reflected.name, "main");
+        assert_eq!(reflected.max_external_resources, 2);
+        assert_eq!(reflected.param_index("count"), Some(0));
+
+        let draw = reflected.node("draw").unwrap();
+        assert_eq!(draw.flags, NodeFlags::GRAPHICS);
+        assert_eq!(draw.params.len(), 2);
+        assert_eq!(draw.params[0].semantic, Semantic::RenderTarget);
+        assert_eq!(draw.params[0].semantic_index, 1);
+        assert_eq!(draw.params[0].access, AccessFlags::RENDER_TARGET);
+        assert!(draw.params[1].is_resource());
+        assert_eq!(
+            usize::from(draw.params[1].type_size),
+            mem::size_of::<BufferView>()
+        );
+    }
+}